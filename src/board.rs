@@ -0,0 +1,173 @@
+use crate::engine::Direction;
+use crate::snake::Position;
+
+/// What happens to the snake when it moves past the edge of the board.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BoundaryRule {
+    /// The edges are walls, leaving the board is fatal.
+    Walls,
+    /// The board is a torus, leaving it on one side enters it on the other.
+    Wrap,
+}
+
+/// Dimensions of the playing field.
+///
+/// `width` and `height` are given in cells while `cell_px` is the edge length
+/// of a single cell on screen. Everything except drawing works in cells,
+/// `to_px` is the only way into pixel space.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BoardConfig {
+    pub width: usize,
+    pub height: usize,
+    pub cell_px: usize,
+    pub boundary: BoundaryRule,
+}
+
+impl BoardConfig {
+    pub const fn new(width: usize, height: usize, cell_px: usize) -> Self {
+        Self {
+            width,
+            height,
+            cell_px,
+            boundary: BoundaryRule::Walls,
+        }
+    }
+
+    pub const fn cells(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub const fn width_px(&self) -> usize {
+        self.width * self.cell_px
+    }
+
+    pub const fn height_px(&self) -> usize {
+        self.height * self.cell_px
+    }
+
+    pub const fn cell_px_f32(&self) -> f32 {
+        self.cell_px as f32
+    }
+
+    pub const fn upper_bound(&self) -> Position {
+        Position {
+            x: self.width as isize,
+            y: self.height as isize,
+        }
+    }
+
+    /// Row major index of `pos`, which has to lie on the board.
+    pub const fn index(&self, pos: Position) -> usize {
+        pos.y as usize * self.width + pos.x as usize
+    }
+
+    pub const fn position(&self, index: usize) -> Position {
+        Position {
+            x: (index % self.width) as isize,
+            y: (index / self.width) as isize,
+        }
+    }
+
+    /// Top left corner of the cell at `pos` on screen.
+    pub const fn to_px(self, pos: Position) -> (f32, f32) {
+        let cell = self.cell_px_f32();
+        (pos.x as f32 * cell, pos.y as f32 * cell)
+    }
+
+    /// Center of the cell at `pos` on screen.
+    pub const fn to_px_center(self, pos: Position) -> (f32, f32) {
+        let (x, y) = self.to_px(pos);
+        let mid = self.cell_px_f32() / 2.0;
+        (x + mid, y + mid)
+    }
+
+    pub const fn contains(&self, pos: Position) -> bool {
+        pos.in_range(Position { x: 0, y: 0 }, self.upper_bound())
+    }
+
+    /// Maps `pos` back onto the board if the edges wrap around. With
+    /// `BoundaryRule::Walls` positions off the board are returned unchanged.
+    pub const fn wrap(&self, pos: Position) -> Position {
+        match self.boundary {
+            BoundaryRule::Walls => pos,
+            BoundaryRule::Wrap => Position {
+                x: pos.x.rem_euclid(self.width as isize),
+                y: pos.y.rem_euclid(self.height as isize),
+            },
+        }
+    }
+
+    /// Direction of the single step leading from `from` to the neighbouring
+    /// cell `to`, which may lie across an edge when the board wraps around.
+    pub fn direction_to(&self, from: Position, to: Position) -> Option<Direction> {
+        Direction::ALL
+            .iter()
+            .copied()
+            .find(|direction| self.wrap(from.step(*direction)) == to)
+    }
+
+    /// Length of the shortest walk between `a` and `b` on an empty board,
+    /// taking the way across the edges into account when they wrap around.
+    pub const fn distance(&self, a: Position, b: Position) -> isize {
+        match self.boundary {
+            BoundaryRule::Walls => a.mhtn_dist(b),
+            BoundaryRule::Wrap => a.wrapped_mhtn_dist(b, self.upper_bound()),
+        }
+    }
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self::new(20, 20, 30)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: isize, y: isize) -> Position {
+        Position { x, y }
+    }
+
+    fn torus(width: usize, height: usize) -> BoardConfig {
+        BoardConfig {
+            boundary: BoundaryRule::Wrap,
+            ..BoardConfig::new(width, height, 1)
+        }
+    }
+
+    #[test]
+    fn wrapping_maps_onto_the_opposite_edge() {
+        let config = torus(10, 6);
+        assert_eq!(config.wrap(pos(10, 3)), pos(0, 3));
+        assert_eq!(config.wrap(pos(-1, 6)), pos(9, 0));
+        assert_eq!(config.wrap(pos(4, 5)), pos(4, 5));
+        assert_eq!(
+            config.direction_to(pos(0, 2), pos(9, 2)),
+            Some(Direction::Left)
+        );
+        assert_eq!(
+            config.direction_to(pos(3, 5), pos(3, 0)),
+            Some(Direction::Down)
+        );
+
+        let config = BoardConfig::new(10, 6, 1);
+        assert_eq!(config.wrap(pos(-1, 6)), pos(-1, 6));
+        assert_eq!(config.direction_to(pos(0, 2), pos(9, 2)), None);
+    }
+
+    #[test]
+    fn distances_take_the_short_way_round_a_torus() {
+        let config = torus(10, 6);
+        assert_eq!(config.distance(pos(0, 0), pos(9, 0)), 1);
+        assert_eq!(config.distance(pos(0, 0), pos(5, 3)), 8);
+        assert_eq!(config.distance(pos(1, 1), pos(8, 4)), 6);
+        assert_eq!(config.distance(pos(2, 5), pos(2, 0)), 1);
+        assert_eq!(config.distance(pos(8, 4), pos(1, 1)), 6);
+
+        let config = BoardConfig::new(10, 6, 1);
+        assert_eq!(config.distance(pos(0, 0), pos(9, 0)), 9);
+        assert_eq!(config.distance(pos(1, 1), pos(8, 4)), 10);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::board::{BoardConfig, BoundaryRule};
+use crate::level::Level;
+use crate::snake::{Apple, Position, Snake};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    /// Offset of a single step in this direction, measured in cells.
+    pub const fn offset(self) -> (isize, isize) {
+        match self {
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+        }
+    }
+}
+
+/// What happened to the snake during a single call to `GameState::step`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum StepOutcome {
+    Moved,
+    AteApple,
+    Died,
+    /// The snake covers every cell of the board, there is no room left for
+    /// another apple.
+    Won,
+}
+
+/// The complete rules of the game without any notion of a window or renderer.
+/// All randomness is drawn from `rng`, so two games started with the same
+/// `seed` and fed the same actions play out identically.
+#[derive(Debug)]
+pub struct GameState {
+    pub config: BoardConfig,
+    pub level: Level,
+    pub snake: Snake,
+    pub apple: Apple,
+    pub score: u32,
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameState {
+    pub fn new(config: BoardConfig, level: Level, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let snake = Self::spawn_snake(&config, &level);
+        let apple = Apple::spawn(&config, &snake, level.walls(), &mut rng)
+            .expect("no room for the first apple");
+        Self {
+            config,
+            level,
+            snake,
+            apple,
+            score: 0,
+            seed,
+            rng,
+        }
+    }
+
+    /// Starts the next game. Its seed is drawn from the current generator so a
+    /// whole session stays reproducible while every game still gets a seed of
+    /// its own that can be replayed with `--seed`.
+    pub fn reset(&mut self) {
+        self.seed = self.rng.gen();
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.snake = Self::spawn_snake(&self.config, &self.level);
+        assert!(self.spawn_new_apple(), "no room for the first apple");
+        self.score = 0;
+    }
+
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub const fn view(&self) -> GameView<'_> {
+        GameView {
+            config: &self.config,
+            level: &self.level,
+            snake: &self.snake,
+            apple: self.apple.pos,
+            seed: self.seed,
+        }
+    }
+
+    /// Advances the game by one tick. `action` is applied before moving, illegal
+    /// directions (reversing into the own tail) are ignored and `None` keeps
+    /// the current heading.
+    pub fn step(&mut self, action: Option<Direction>) -> StepOutcome {
+        if let Some(direction) = action {
+            self.snake.set_direction(direction);
+        }
+
+        self.snake.update();
+        if self.config.boundary == BoundaryRule::Wrap {
+            self.snake.teleport_if_outside(&self.config);
+        }
+        if self.is_outside() || self.hit_wall() || self.snake.ate_itself() {
+            return StepOutcome::Died;
+        }
+        if self.ate_apple() {
+            self.score += 1;
+            self.snake.grow();
+            if !self.spawn_new_apple() {
+                return StepOutcome::Won;
+            }
+            return StepOutcome::AteApple;
+        }
+        StepOutcome::Moved
+    }
+
+    fn is_outside(&self) -> bool {
+        let head = self.snake.head().unwrap();
+        self.config.boundary == BoundaryRule::Walls && !self.config.contains(*head)
+    }
+
+    fn hit_wall(&self) -> bool {
+        let head = self.snake.head().unwrap();
+        self.level.is_wall(*head)
+    }
+
+    fn ate_apple(&self) -> bool {
+        let head = self.snake.head().unwrap();
+        self.apple.pos == *head
+    }
+
+    fn spawn_snake(config: &BoardConfig, level: &Level) -> Snake {
+        level.start.map_or_else(|| Snake::new(config), Snake::at)
+    }
+
+    /// Returns `false` if the board is full and no apple could be placed.
+    fn spawn_new_apple(&mut self) -> bool {
+        match Apple::spawn(&self.config, &self.snake, self.level.walls(), &mut self.rng) {
+            Some(apple) => {
+                self.apple = apple;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Read only snapshot of a `GameState`, everything a computer player gets to
+/// see before deciding on its next move.
+#[derive(Debug, Clone, Copy)]
+pub struct GameView<'a> {
+    pub config: &'a BoardConfig,
+    pub level: &'a Level,
+    pub snake: &'a Snake,
+    pub apple: Position,
+    pub seed: u64,
+}
+
+impl GameView<'_> {
+    pub fn head(&self) -> Position {
+        *self.snake.head().unwrap()
+    }
+
+    /// Whether moving onto `pos` would kill the snake right away. The end of
+    /// the tail is out of the way by then unless the snake grows.
+    pub fn is_blocked(&self, pos: Position) -> bool {
+        let leaving = usize::from(self.snake.end_moves_on());
+        !self.config.contains(pos)
+            || self.level.is_wall(pos)
+            || self
+                .snake
+                .tail
+                .iter()
+                .rev()
+                .skip(leaving)
+                .any(|node| *node == pos)
+    }
+
+    /// Directions the snake may legally take without dying on the next tick.
+    pub fn safe_directions(&self) -> impl Iterator<Item = Direction> + '_ {
+        let head = self.head();
+        Direction::ALL.iter().copied().filter(move |direction| {
+            self.snake.direction_is_legal(*direction)
+                && !self.is_blocked(self.config.wrap(head.step(*direction)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game on an empty `width`x`height` board with the snake lying on
+    /// `cells`, head first, and the apple on `apple`.
+    fn game(
+        (width, height): (usize, usize),
+        cells: &[(isize, isize)],
+        direction: Direction,
+        (x, y): (isize, isize),
+    ) -> GameState {
+        let config = BoardConfig::new(width, height, 1);
+        let mut state = GameState::new(config, Level::empty(&config), 0);
+        state.snake = Snake {
+            tail: cells.iter().map(|&(x, y)| Position { x, y }).collect(),
+            direction: Some(direction),
+        };
+        state.apple.pos = Position { x, y };
+        state
+    }
+
+    fn head(state: &GameState) -> Position {
+        *state.snake.head().unwrap()
+    }
+
+    /// Heads for the apple on the shortest safe move until the game ends or
+    /// `moves` ran out, and returns where every apple was placed.
+    fn chase(state: &mut GameState, moves: usize) -> Vec<Position> {
+        let mut apples = vec![state.apple.pos];
+        for _ in 0..moves {
+            let view = state.view();
+            let action = view.safe_directions().min_by_key(|direction| {
+                let next = view.config.wrap(view.head().step(*direction));
+                view.config.distance(next, view.apple)
+            });
+            match state.step(action) {
+                StepOutcome::AteApple => apples.push(state.apple.pos),
+                StepOutcome::Moved => {}
+                StepOutcome::Died | StepOutcome::Won => break,
+            }
+        }
+        apples
+    }
+
+    const ROW: [(isize, isize); 7] = [(6, 1), (5, 1), (4, 1), (3, 1), (2, 1), (1, 1), (0, 1)];
+
+    #[test]
+    fn eating_an_apple_grows_the_snake() {
+        let mut state = game((10, 3), &ROW, Direction::Right, (7, 1));
+        assert_eq!(state.step(None), StepOutcome::AteApple);
+        assert_eq!(state.score, 1);
+        assert_eq!(state.snake.tail.len(), ROW.len() + 1);
+        assert!(!state.snake.tail.contains(&state.apple.pos));
+
+        assert_eq!(state.step(None), StepOutcome::Moved);
+        assert_eq!(head(&state), Position { x: 8, y: 1 });
+        // The end of the tail waited for a tick while the snake grew.
+        assert_eq!(state.snake.tail.back(), Some(&Position { x: 1, y: 1 }));
+        assert_eq!(state.snake.tail.len(), ROW.len() + 1);
+    }
+
+    #[test]
+    fn same_seed_and_actions_play_the_same_game() {
+        let config = BoardConfig::new(8, 6, 1);
+        let mut first = GameState::new(config, Level::empty(&config), 42);
+        let mut second = GameState::new(config, Level::empty(&config), 42);
+        let apples = chase(&mut first, 200);
+        assert!(apples.len() > 5, "only {} apples", apples.len());
+        assert_eq!(chase(&mut second, 200), apples);
+
+        let mut other = GameState::new(config, Level::empty(&config), 43);
+        assert_ne!(chase(&mut other, 200), apples);
+    }
+
+    #[test]
+    fn reset_games_replay_from_their_seed() {
+        let config = BoardConfig::new(8, 6, 1);
+        let mut state = GameState::new(config, Level::empty(&config), 42);
+        chase(&mut state, 50);
+        state.reset();
+        assert_ne!(state.seed(), 42);
+
+        let mut replay = GameState::new(config, Level::empty(&config), state.seed());
+        assert_eq!(replay.snake.tail, state.snake.tail);
+        assert_eq!(replay.score, 0);
+        assert_eq!(chase(&mut replay, 200), chase(&mut state, 200));
+    }
+
+    #[test]
+    fn filling_the_board_wins() {
+        let cells = [(0, 1), (0, 0), (1, 0)];
+        let mut state = game((2, 2), &cells, Direction::Down, (1, 1));
+        assert_eq!(state.step(Some(Direction::Right)), StepOutcome::Won);
+        assert_eq!(state.score, 1);
+    }
+
+    #[test]
+    fn leaving_the_board_dies() {
+        let mut state = game((7, 3), &ROW, Direction::Right, (0, 0));
+        assert_eq!(state.step(None), StepOutcome::Died);
+
+        let mut state = game((7, 3), &ROW, Direction::Right, (0, 0));
+        assert_eq!(state.step(Some(Direction::Up)), StepOutcome::Moved);
+        assert_eq!(state.step(None), StepOutcome::Died);
+    }
+
+    #[test]
+    fn wrapping_edges_lead_onto_the_opposite_side() {
+        let cells = [(9, 1), (8, 1), (7, 1), (6, 1), (5, 1), (4, 1), (3, 1)];
+        let mut state = game((10, 3), &cells, Direction::Right, (5, 0));
+        let safe = |state: &GameState| state.view().safe_directions().collect::<Vec<_>>();
+        assert_eq!(safe(&state), [Direction::Up, Direction::Down]);
+
+        state.config.boundary = BoundaryRule::Wrap;
+        let all = [Direction::Up, Direction::Down, Direction::Right];
+        assert_eq!(safe(&state), all);
+        assert_eq!(state.step(None), StepOutcome::Moved);
+        assert_eq!(head(&state), Position { x: 0, y: 1 });
+        assert_eq!(state.step(Some(Direction::Up)), StepOutcome::Moved);
+        assert_eq!(state.step(None), StepOutcome::Moved);
+        assert_eq!(head(&state), Position { x: 0, y: 2 });
+    }
+
+    #[test]
+    fn biting_the_own_tail_dies() {
+        let cells = [(2, 1), (2, 2), (1, 2), (1, 1), (1, 0), (2, 0), (3, 0)];
+        let mut state = game((10, 3), &cells, Direction::Up, (9, 2));
+        assert_eq!(state.step(Some(Direction::Left)), StepOutcome::Died);
+
+        // Chasing the end of the tail is fine, it moves out of the way in time.
+        let cells = [
+            (1, 0),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 1),
+            (0, 0),
+        ];
+        let mut state = game((3, 3), &cells, Direction::Left, (1, 1));
+        assert_eq!(state.step(None), StepOutcome::Moved);
+        assert_eq!(head(&state), Position { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn reversing_is_ignored() {
+        let mut state = game((10, 3), &ROW, Direction::Right, (0, 0));
+        assert_eq!(state.step(Some(Direction::Left)), StepOutcome::Moved);
+        assert_eq!(state.snake.direction, Some(Direction::Right));
+        assert_eq!(head(&state), Position { x: 7, y: 1 });
+
+        assert_eq!(state.step(Some(Direction::Down)), StepOutcome::Moved);
+        assert_eq!(head(&state), Position { x: 7, y: 2 });
+    }
+}
@@ -1,4 +1,9 @@
-use crate::search::*;
+use bake::board::BoardConfig;
+use bake::engine::{Direction, GameState, StepOutcome};
+use bake::options::Options;
+use bake::pathfinding::{self, PathFinder};
+use bake::search::{Path, SearchTrace, Searcher};
+use bake::strategy::{self, Strategy};
 use coffee::graphics::{Color, Frame, Mesh, Point, Rectangle, Shape, Window};
 use coffee::input::keyboard::KeyCode;
 use coffee::input::{self, keyboard, ButtonState, Input};
@@ -6,16 +11,7 @@ use coffee::load::Task;
 use coffee::ui::{Column, Element, Justify, Renderer, Text, UserInterface};
 use coffee::{Game, Timer};
 
-use crate::snake::{Apple, Position, Snake, Tail};
-
-pub const WINDOW_SIZE_X: usize = 600;
-pub const WINDOW_SIZE_Y: usize = WINDOW_SIZE_X;
-
-pub const WINDOW_SIZE_X_F32: f32 = WINDOW_SIZE_X as f32;
-pub const WINDOW_SIZE_Y_F32: f32 = WINDOW_SIZE_Y as f32;
-
-pub const NODE_SIZE: usize = 30;
-pub const NODE_SIZE_F32: f32 = NODE_SIZE as f32;
+use bake::snake::{Position, Tail};
 
 const GRID_COLOR: Color = Color {
     r: 0.12941,
@@ -38,6 +34,49 @@ const PATH_COLOR: Color = Color {
     a: 0.2,
 };
 
+const OPEN_COLOR: Color = Color {
+    r: 0.0,
+    g: 0.74902,
+    b: 1.0,
+    a: 0.3,
+};
+
+const CLOSED_COLOR: Color = Color {
+    r: 0.41176,
+    g: 0.41176,
+    b: 0.41176,
+    a: 0.5,
+};
+
+const CURRENT_COLOR: Color = Color {
+    r: 1.0,
+    g: 0.84314,
+    b: 0.0,
+    a: 0.8,
+};
+
+const POCKET_COLOR: Color = Color {
+    r: 1.0,
+    g: 0.64706,
+    b: 0.0,
+    a: 0.3,
+};
+
+/// Drawn more opaque the more a cell costs to step onto.
+const TERRAIN_COLOR: Color = Color {
+    r: 0.54510,
+    g: 0.35294,
+    b: 0.16863,
+    a: 1.0,
+};
+
+const WALL_COLOR: Color = Color {
+    r: 0.52549,
+    g: 0.59608,
+    b: 0.61961,
+    a: 1.0,
+};
+
 const SNAKE_COLOR: Color = Color {
     r: 0.23922,
     g: 0.78039,
@@ -54,178 +93,419 @@ const BG_COLOR: Color = Color {
 
 #[derive(Debug)]
 pub struct SnakeGame {
-    snake: Snake,
-    apple: Apple,
+    state: GameState,
     speed: u32,
     tick: u32,
-    score: u32,
     mode: Mode,
-    solver: Solver,
+    /// The computer player, only built once a computer mode is entered.
+    strategy: Option<Box<dyn Strategy>>,
+    strategy_index: usize,
+    searcher: Searcher,
+    /// Solves the maze in `Mode::Maze`.
+    finder: Box<dyn PathFinder>,
     is_finished: bool,
     dump_index: usize,
+    maze_path: Option<Path>,
+    maze_cursor: usize,
+    /// The search shown in `DrawMode::Search`.
+    replay: Option<Replay>,
+    /// Why `switch_strategy` skipped strategies on the way to the current one.
+    notice: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum Mode {
     Computer(DrawMode),
     Human,
+    /// Animates the A* path from the `S` to the `E` cell of the level instead
+    /// of playing.
+    Maze,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum DrawMode {
     Normal,
     Path,
+    /// Highlights the pockets of free cells too small to hold the snake.
+    Regions,
+    /// Pauses the game and replays an A* search from the head to the apple
+    /// one expansion at a time.
+    Search,
 }
 
-pub const GAME_LOWER_BOUND: Position = Position { x: 0, y: 0 };
+/// A traced search and how much of it is shown.
+#[derive(Debug)]
+struct Replay {
+    trace: SearchTrace,
+    /// Number of expansions shown, the path appears once all of them are.
+    cursor: usize,
+    paused: bool,
+}
 
-pub const GAME_UPPER_BOUND: Position = Position {
-    x: WINDOW_SIZE_X as isize,
-    y: WINDOW_SIZE_Y as isize,
-};
+impl Replay {
+    const fn is_done(&self) -> bool {
+        self.cursor == self.trace.steps.len()
+    }
+
+    fn step_forward(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.trace.steps.len());
+    }
+
+    const fn step_back(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+}
 
 impl SnakeGame {
-    pub fn new() -> Self {
-        let snake = Snake::new();
-        let mut solver = Solver::new();
-        solver.gen_zig_zag_path();
-        solver.init(&snake.tail);
-        Self {
-            snake,
+    pub fn new(options: &Options) -> Self {
+        let config = options.board;
+        let state = GameState::new(config, options.level(), options.seed());
+        let mut searcher = Searcher::new(config);
+        searcher.set_walls(state.level.walls());
+        let mut maze_searcher = Searcher::new(config);
+        maze_searcher.set_walls(state.level.walls());
+        maze_searcher.set_neighbourhood(options.neighbourhood);
+        maze_searcher.set_terrain(state.level.costs());
+        let mut game = Self {
+            state,
             speed: 20,
             tick: 0,
-            score: 0,
             mode: Mode::Human,
-            apple: Apple::new(),
             is_finished: false,
             dump_index: 0,
-            solver,
+            strategy: None,
+            strategy_index: options.strategy,
+            searcher,
+            finder: pathfinding::create(options.finder, maze_searcher),
+            maze_path: None,
+            maze_cursor: 0,
+            replay: None,
+            notice: None,
+        };
+        if options.maze {
+            game.toggle_maze();
         }
+        game
     }
 
     fn reset(&mut self) {
-        self.snake = Snake::new();
-        self.spawn_new_apple();
-        self.score = 0;
+        self.state.reset();
         self.mode = Mode::Human;
-        self.solver.init(&self.snake.tail);
+        self.strategy = None;
+    }
+
+    /// Switches to the strategy at `index` in `strategy::STRATEGIES` or, if
+    /// that one can't play on the current board, to the next one that can.
+    /// The ones skipped on the way are listed in `notice`.
+    fn switch_strategy(&mut self, index: usize) {
+        let count = strategy::STRATEGIES.len();
+        let mut skipped = Vec::new();
+        for offset in 0..count {
+            let index = (index + offset) % count;
+            match strategy::create(index, &self.state.view()) {
+                Ok(strategy) => {
+                    self.strategy = Some(strategy);
+                    self.strategy_index = index;
+                    break;
+                }
+                Err(err) => skipped.push(format!("{}: {}", strategy::name(index), err)),
+            }
+        }
+        self.notice = (!skipped.is_empty()).then(|| format!("Skipped {}", skipped.join(", ")));
+    }
+
+    fn next_strategy(&mut self) {
+        self.switch_strategy(self.strategy_index + 1);
     }
 
     fn toggle_mode(&mut self) {
         match self.mode {
-            Mode::Human => self.mode = Mode::Computer(DrawMode::Normal),
-            Mode::Computer(_) => self.mode = Mode::Human,
+            Mode::Human => {
+                if self.strategy.is_none() {
+                    self.switch_strategy(self.strategy_index);
+                }
+                self.mode = Mode::Computer(DrawMode::Normal);
+            }
+            Mode::Computer(_) | Mode::Maze => self.mode = Mode::Human,
+        }
+    }
+
+    fn toggle_maze(&mut self) {
+        if self.mode == Mode::Maze {
+            self.mode = Mode::Human;
+            return;
+        }
+        let level = &self.state.level;
+        if let (Some(start), Some(goal)) = (level.start, level.goal) {
+            self.maze_path = self.finder.find_path(start, goal, &Tail::new());
+            self.maze_cursor = 0;
+            self.tick = 0;
+            self.mode = Mode::Maze;
+        }
+    }
+
+    /// Reveals one more cell of the maze path every `speed` ticks.
+    fn animate_maze(&mut self) {
+        self.tick += 1;
+        if self.tick.is_multiple_of(self.speed) {
+            let len = self
+                .maze_path
+                .as_ref()
+                .map_or(0, |path| path.cells.len() - 1);
+            self.maze_cursor = (self.maze_cursor + 1).min(len);
         }
     }
 
     fn toggle_draw_mode(&mut self) {
         match self.mode {
             Mode::Computer(DrawMode::Normal) => self.mode = Mode::Computer(DrawMode::Path),
-            Mode::Computer(DrawMode::Path) => self.mode = Mode::Computer(DrawMode::Normal),
+            Mode::Computer(DrawMode::Path) => self.mode = Mode::Computer(DrawMode::Regions),
+            Mode::Computer(DrawMode::Regions) => {
+                let view = self.state.view();
+                let trace = self
+                    .searcher
+                    .trace_a_star(view.head(), view.apple, &view.snake.tail);
+                self.replay = Some(Replay {
+                    trace,
+                    cursor: 0,
+                    paused: false,
+                });
+                self.tick = 0;
+                self.mode = Mode::Computer(DrawMode::Search);
+            }
+            Mode::Computer(DrawMode::Search) => {
+                self.replay = None;
+                self.mode = Mode::Computer(DrawMode::Normal);
+            }
             _ => (),
         }
     }
 
-    fn ate_apple(&self) -> bool {
-        let head = self.snake.head().unwrap();
-        self.apple.pos == *head
+    /// Shows one more expansion of the replayed search every `speed` ticks
+    /// unless it is paused.
+    fn animate_replay(&mut self) {
+        self.tick += 1;
+        if let Some(replay) = &mut self.replay {
+            if !replay.paused && self.tick.is_multiple_of(self.speed) {
+                replay.step_forward();
+            }
+        }
     }
 
-    fn spawn_new_apple(&mut self) {
-        loop {
-            let apple = Apple::new();
-            if !self.snake.is_inside(apple.pos) {
-                self.apple = apple;
-                return;
+    const fn pause_replay(&mut self) {
+        if let Some(replay) = &mut self.replay {
+            replay.paused = !replay.paused;
+        }
+    }
+
+    /// Pauses the replay and moves it by a single expansion.
+    fn step_replay(&mut self, forward: bool) {
+        if let Some(replay) = &mut self.replay {
+            replay.paused = true;
+            if forward {
+                replay.step_forward();
+            } else {
+                replay.step_back();
             }
         }
     }
 
     fn draw_tail(&self, mesh: &mut Mesh) {
-        for node in &self.snake.tail {
+        let config = &self.state.config;
+        let cell = config.cell_px_f32();
+        for node in &self.state.snake.tail {
+            let (x, y) = config.to_px(*node);
             mesh.fill(
                 Shape::Rectangle(Rectangle {
-                    x: node.x as f32,
-                    y: node.y as f32,
-                    width: NODE_SIZE_F32,
-                    height: NODE_SIZE_F32,
+                    x,
+                    y,
+                    width: cell,
+                    height: cell,
                 }),
                 SNAKE_COLOR,
             );
         }
     }
 
+    fn draw_apple(&self, mesh: &mut Mesh) {
+        let config = &self.state.config;
+        let (x, y) = config.to_px(self.state.apple.pos);
+        mesh.fill(
+            Shape::Rectangle(Rectangle {
+                x,
+                y,
+                width: config.cell_px_f32(),
+                height: config.cell_px_f32(),
+            }),
+            Color::RED,
+        );
+    }
+
+    fn draw_walls(&self, mesh: &mut Mesh) {
+        let config = &self.state.config;
+        let cell = config.cell_px_f32();
+        for wall in self.state.level.walls() {
+            let (x, y) = config.to_px(*wall);
+            mesh.fill(
+                Shape::Rectangle(Rectangle {
+                    x,
+                    y,
+                    width: cell,
+                    height: cell,
+                }),
+                WALL_COLOR,
+            );
+        }
+    }
+
+    fn draw_terrain(&self, mesh: &mut Mesh) {
+        let config = &self.state.config;
+        let cell = config.cell_px_f32();
+        for (index, cost) in self.state.level.costs().iter().enumerate() {
+            if *cost <= 1 {
+                continue;
+            }
+            let (x, y) = config.to_px(config.position(index));
+            mesh.fill(
+                Shape::Rectangle(Rectangle {
+                    x,
+                    y,
+                    width: cell,
+                    height: cell,
+                }),
+                Color {
+                    a: (*cost as f32 / 10.0).min(1.0),
+                    ..TERRAIN_COLOR
+                },
+            );
+        }
+    }
+
     fn draw_ham_path(&self, mesh: &mut Mesh) {
         let mut points = vec![];
-        let half_node = (NODE_SIZE / 2) as isize;
-        for pos in &self.solver.path {
-            let x = pos.x + half_node;
-            let y = pos.y + half_node;
-            let point = Point::new(x as f32, y as f32);
-            points.push(point);
+        let cycle = self.strategy.as_ref().and_then(|strategy| strategy.cycle());
+        for pos in cycle.unwrap_or_default() {
+            let (x, y) = self.state.config.to_px_center(*pos);
+            points.push(Point::new(x, y));
         }
         let line = Shape::Polyline { points };
 
         mesh.stroke(line, HAM_PATH_COLOR, 1.0);
     }
 
-    fn draw_grid(mesh: &mut Mesh) {
-        let x_bound = WINDOW_SIZE_X / NODE_SIZE as usize;
-        let y_bound = WINDOW_SIZE_Y / NODE_SIZE as usize;
-        for i in 0..x_bound {
+    fn draw_regions(&mut self, mesh: &mut Mesh) {
+        let view = self.state.view();
+        let regions = self.searcher.regions(&view);
+        let config = view.config;
+        let cell = config.cell_px_f32();
+        for index in 0..config.cells() {
+            let pos = config.position(index);
+            let size = regions.size_at(config, pos);
+            if size == 0 || size >= view.snake.tail.len() {
+                continue;
+            }
+            let (x, y) = config.to_px(pos);
+            mesh.fill(
+                Shape::Rectangle(Rectangle {
+                    x,
+                    y,
+                    width: cell,
+                    height: cell,
+                }),
+                POCKET_COLOR,
+            );
+        }
+    }
+
+    /// The closed and open cells of the replayed search, the cell expanded
+    /// last and, once the search is done, the path it found.
+    fn draw_replay(&self, mesh: &mut Mesh) {
+        let Some(replay) = &self.replay else {
+            return;
+        };
+        let config = &self.state.config;
+        let cell = config.cell_px_f32();
+        let mut fill = |pos: Position, color: Color| {
+            let (x, y) = config.to_px(pos);
+            mesh.fill(
+                Shape::Rectangle(Rectangle {
+                    x,
+                    y,
+                    width: cell,
+                    height: cell,
+                }),
+                color,
+            );
+        };
+
+        let (open, closed) = replay.trace.sets_after(replay.cursor);
+        for pos in closed {
+            fill(pos, CLOSED_COLOR);
+        }
+        for pos in open {
+            fill(pos, OPEN_COLOR);
+        }
+        if let Some(step) = replay.cursor.checked_sub(1) {
+            fill(replay.trace.steps[step].current, CURRENT_COLOR);
+        }
+        if let (true, Some(path)) = (replay.is_done(), &replay.trace.path) {
+            self.draw_path(&path.cells, mesh);
+        }
+    }
+
+    fn draw_grid(config: &BoardConfig, mesh: &mut Mesh) {
+        let cell = config.cell_px_f32();
+        let (width, height) = (config.width_px() as f32, config.height_px() as f32);
+        for i in 0..config.width {
             let i = i as f32;
             let line = Shape::Polyline {
-                points: vec![
-                    Point::new(i * NODE_SIZE_F32, 0.0),
-                    Point::new(i * NODE_SIZE_F32, WINDOW_SIZE_Y_F32),
-                ],
+                points: vec![Point::new(i * cell, 0.0), Point::new(i * cell, height)],
             };
             mesh.stroke(line, GRID_COLOR, 1.0);
         }
-        for i in 0..y_bound {
+        for i in 0..config.height {
             let i = i as f32;
             let line = Shape::Polyline {
-                points: vec![
-                    Point::new(0.0, i * NODE_SIZE_F32),
-                    Point::new(WINDOW_SIZE_X_F32, i * NODE_SIZE_F32),
-                ],
+                points: vec![Point::new(0.0, i * cell), Point::new(width, i * cell)],
             };
             mesh.stroke(line, GRID_COLOR, 1.0);
         }
     }
 
-    fn draw_path(&self, path: &Tail, mesh: &mut Mesh) {
-        let mid = NODE_SIZE_F32 / 2.0;
+    fn draw_maze(&self, mesh: &mut Mesh) {
+        if let Some(path) = &self.maze_path {
+            self.draw_path(&path.cells[..=self.maze_cursor], mesh);
+        }
+    }
 
-        for pos in path {
+    /// Highlights the cells of `path` after the first one, the cell the path
+    /// was searched from, and connects all of them with a line.
+    fn draw_path(&self, path: &[Position], mesh: &mut Mesh) {
+        let config = &self.state.config;
+        let cell = config.cell_px_f32();
+
+        for pos in path.iter().skip(1) {
+            let (x, y) = config.to_px(*pos);
             mesh.fill(
                 Shape::Rectangle(Rectangle {
-                    x: pos.x as f32,
-                    y: pos.y as f32,
-                    width: NODE_SIZE_F32,
-                    height: NODE_SIZE_F32,
+                    x,
+                    y,
+                    width: cell,
+                    height: cell,
                 }),
                 PATH_COLOR,
             );
         }
 
-        let mut points = path
+        let points = path
             .iter()
-            .map(|p| (p.x as f32, p.y as f32))
-            .map(|(x, y)| Point::new(x + mid, y + mid))
+            .map(|p| config.to_px_center(*p))
+            .map(|(x, y)| Point::new(x, y))
             .collect::<Vec<Point>>();
-        let head = self.snake.head().unwrap();
-
-        points.push(Point::new(head.x as f32 + mid, head.y as f32 + mid));
         let line = Shape::Polyline { points };
 
         mesh.stroke(line, Color::RED, 2.0);
     }
-
-    fn is_outside(&self) -> bool {
-        let head = self.snake.head().unwrap();
-        !head.in_range(GAME_LOWER_BOUND, GAME_UPPER_BOUND)
-    }
 }
 
 impl Game for SnakeGame {
@@ -234,59 +514,77 @@ impl Game for SnakeGame {
     type LoadingScreen = ();
 
     fn load(_window: &Window) -> Task<Self> {
-        Task::succeed(Self::new)
+        Task::succeed(|| Self::new(Options::get()))
     }
 
     fn draw(&mut self, frame: &mut Frame, _timer: &Timer) {
         let mut mesh = Mesh::new();
         frame.clear(BG_COLOR);
-        Self::draw_grid(&mut mesh);
-        self.draw_tail(&mut mesh);
-        self.apple.draw(&mut mesh);
-        if self.mode == Mode::Computer(DrawMode::Path) {
-            //self.draw_path(&mut mesh);
-            self.draw_ham_path(&mut mesh);
+        Self::draw_grid(&self.state.config, &mut mesh);
+        self.draw_terrain(&mut mesh);
+        self.draw_walls(&mut mesh);
+        if self.mode == Mode::Maze {
+            self.draw_maze(&mut mesh);
+        } else {
+            self.draw_tail(&mut mesh);
+            self.draw_apple(&mut mesh);
+        }
+        match self.mode {
+            Mode::Computer(DrawMode::Path) => self.draw_ham_path(&mut mesh),
+            Mode::Computer(DrawMode::Regions) => self.draw_regions(&mut mesh),
+            Mode::Computer(DrawMode::Search) => self.draw_replay(&mut mesh),
+            _ => {}
         }
         mesh.draw(&mut frame.as_target())
     }
 
     fn update(&mut self, _: &Window) {
-        if let Mode::Computer(_) = self.mode {
-            self.solver
-                .make_move(&self.snake.tail, self.apple.pos)
-                .expect("failed to make move in solver");
-            self.snake.direction = self.solver.make_move(&self.snake.tail, self.apple.pos);
-        }
+        let action = match self.mode {
+            Mode::Computer(DrawMode::Search) => {
+                self.animate_replay();
+                return;
+            }
+            Mode::Computer(_) => {
+                let view = self.state.view();
+                self.strategy
+                    .as_mut()
+                    .map(|strategy| strategy.next_direction(&view))
+            }
+            Mode::Human => None,
+            Mode::Maze => {
+                self.animate_maze();
+                return;
+            }
+        };
 
-        self.snake.update();
-        if self.is_outside() || self.snake.ate_itself() {
-            self.reset();
-            return;
-        }
-        if self.ate_apple() {
-            self.score += 1;
-            self.snake.add_node(self.apple.pos);
-            self.spawn_new_apple();
+        match self.state.step(action) {
+            StepOutcome::Died | StepOutcome::Won => self.reset(),
+            StepOutcome::Moved | StepOutcome::AteApple => {}
         }
     }
 
     fn interact(&mut self, input: &mut Self::Input, _window: &mut Window) {
         match input.key_code {
             Some(KeyCode::W) | Some(KeyCode::Up) | Some(KeyCode::K) => {
-                self.snake.set_direction(Direction::Up);
+                self.state.snake.set_direction(Direction::Up);
             }
             Some(KeyCode::A) | Some(KeyCode::Left) | Some(KeyCode::H) => {
-                self.snake.set_direction(Direction::Left);
+                self.state.snake.set_direction(Direction::Left);
             }
             Some(KeyCode::S) | Some(KeyCode::Down) | Some(KeyCode::J) => {
-                self.snake.set_direction(Direction::Down);
+                self.state.snake.set_direction(Direction::Down);
             }
             Some(KeyCode::D) | Some(KeyCode::Right) | Some(KeyCode::L) => {
-                self.snake.set_direction(Direction::Right);
+                self.state.snake.set_direction(Direction::Right);
             }
             Some(KeyCode::Q) => self.toggle_mode(),
             Some(KeyCode::T) => self.toggle_draw_mode(),
+            Some(KeyCode::M) => self.toggle_maze(),
+            Some(KeyCode::C) => self.next_strategy(),
             Some(KeyCode::R) => self.reset(),
+            Some(KeyCode::Space) => self.pause_replay(),
+            Some(KeyCode::Period) => self.step_replay(true),
+            Some(KeyCode::Comma) => self.step_replay(false),
             Some(KeyCode::Escape) => self.is_finished = true,
             _ => {}
         }
@@ -297,25 +595,6 @@ impl Game for SnakeGame {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    pub const fn opposite(self) -> Self {
-        match self {
-            Self::Up => Self::Down,
-            Self::Down => Self::Up,
-            Self::Left => Self::Right,
-            Self::Right => Self::Left,
-        }
-    }
-}
-
 #[derive(Debug)]
 pub struct CustomInput {
     key_code: Option<KeyCode>,
@@ -348,13 +627,43 @@ impl UserInterface for SnakeGame {
     fn react(&mut self, _: Self::Message, _: &mut Window) {}
 
     fn layout(&mut self, window: &Window) -> Element<Self::Message> {
-        let score = format!("Score: {}", self.score);
-        let direction = match self.snake.direction {
+        let score = format!("Score: {}", self.state.score);
+        let seed = format!("Seed: {}", self.state.seed());
+        let player = match self.mode {
+            Mode::Computer(_) => format!("Player: {}", strategy::name(self.strategy_index)),
+            _ => "Player: human".into(),
+        };
+        let direction = match self.state.snake.direction {
             Some(dir) => format!("Direction: {:#?}", dir),
             None => "Standing still".into(),
         };
+        if self.mode == Mode::Maze {
+            let maze = match &self.maze_path {
+                Some(path) => format!(
+                    "Maze path: {} / {} cells, cost {}",
+                    self.maze_cursor,
+                    path.cells.len() - 1,
+                    path.cost
+                ),
+                None => "No path from S to E".into(),
+            };
+            let stats = self.finder.stats();
+            let search = format!(
+                "Search: {} expanded, {} generated, peak open {}, {:.2?}",
+                stats.expanded, stats.generated, stats.peak_open, stats.duration
+            );
+            return Column::new()
+                .padding(20)
+                .spacing(20)
+                .width(window.width() as u32)
+                .height(window.height() as u32)
+                .justify_content(Justify::End)
+                .push(Text::new(&maze))
+                .push(Text::new(&search))
+                .into();
+        }
 
-        Column::new()
+        let mut hud = Column::new()
             .padding(20)
             .spacing(20)
             .width(window.width() as u32)
@@ -362,6 +671,33 @@ impl UserInterface for SnakeGame {
             .justify_content(Justify::End)
             .push(Text::new(&score))
             .push(Text::new(&direction))
-            .into()
+            .push(Text::new(&seed))
+            .push(Text::new(&player));
+        if let Some(notice) = &self.notice {
+            hud = hud.push(Text::new(notice));
+        }
+        let stats = self
+            .strategy
+            .as_ref()
+            .and_then(|strategy| strategy.search_stats());
+        if let (Mode::Computer(_), Some(stats)) = (self.mode, stats) {
+            hud = hud.push(Text::new(&format!(
+                "Search: {} runs, {:.1} expanded each, peak open {}, {:.2?}",
+                stats.searches,
+                stats.expanded_per_search(),
+                stats.peak_open,
+                stats.duration
+            )));
+        }
+        if let (Mode::Computer(DrawMode::Search), Some(replay)) = (self.mode, &self.replay) {
+            let progress = if replay.paused { "paused" } else { "running" };
+            hud = hud.push(Text::new(&format!(
+                "A* step {} / {}, {}: space pauses, , and . step",
+                replay.cursor,
+                replay.trace.steps.len(),
+                progress
+            )));
+        }
+        hud.into()
     }
 }
@@ -0,0 +1,84 @@
+use crate::engine::{GameState, StepOutcome};
+use crate::options::Options;
+use crate::search::SearchStats;
+use crate::strategy::{self, Strategy};
+use anyhow::Result;
+
+/// A game counts as stalled once the computer player went this many moves
+/// per board cell without eating an apple.
+const STALL_MOVES_PER_CELL: usize = 4;
+
+/// Plays `games` games with the strategy chosen in `options` without opening
+/// a window and prints one line per game followed by a summary.
+pub fn run(options: &Options, games: usize) -> Result<()> {
+    let name = strategy::name(options.strategy);
+    let mut state = GameState::new(options.board, options.level(), options.seed());
+    let (mut total_score, mut total_steps, mut wins) = (0, 0, 0);
+    let mut total_stats: Option<SearchStats> = None;
+
+    for game in 0..games {
+        if game > 0 {
+            state.reset();
+        }
+        let mut player = strategy::create(options.strategy, &state.view())?;
+        let seed = state.seed();
+        let (steps, outcome) = play(&mut state, player.as_mut());
+        let outcome = match outcome {
+            Some(StepOutcome::Won) => {
+                wins += 1;
+                "won"
+            }
+            Some(_) => "died",
+            None => "stalled",
+        };
+        println!(
+            "game {}: seed {} score {} steps {} {}",
+            game, seed, state.score, steps, outcome
+        );
+        if let Some(stats) = player.search_stats() {
+            println!("game {game}: {stats}");
+            total_stats
+                .get_or_insert_with(SearchStats::default)
+                .add(&stats);
+        }
+        total_score += state.score as usize;
+        total_steps += steps;
+    }
+
+    let games = games.max(1) as f32;
+    println!(
+        "{}: {} wins, {:.1} apples and {:.1} moves per game, {:.1} moves per apple",
+        name,
+        wins,
+        total_score as f32 / games,
+        total_steps as f32 / games,
+        total_steps as f32 / total_score.max(1) as f32,
+    );
+    if let Some(stats) = total_stats {
+        println!(
+            "{}: {}, {:.1} expanded per search",
+            name,
+            stats,
+            stats.expanded_per_search()
+        );
+    }
+    Ok(())
+}
+
+/// Runs a single game to its end and returns the number of moves made and the
+/// final outcome, `None` if the game stalled.
+pub fn play(state: &mut GameState, player: &mut dyn Strategy) -> (usize, Option<StepOutcome>) {
+    let stall_limit = state.config.cells() * STALL_MOVES_PER_CELL;
+    let (mut steps, mut since_apple) = (0, 0);
+    while since_apple < stall_limit {
+        let direction = player.next_direction(&state.view());
+        steps += 1;
+        since_apple += 1;
+        match state.step(Some(direction)) {
+            StepOutcome::Moved => {}
+            StepOutcome::AteApple => since_apple = 0,
+            outcome @ (StepOutcome::Died | StepOutcome::Won) => return (steps, Some(outcome)),
+        }
+    }
+    (steps, None)
+}
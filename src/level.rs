@@ -0,0 +1,205 @@
+use crate::board::BoardConfig;
+use crate::snake::Position;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+const WALL: char = '#';
+const FLOOR: char = ' ';
+const START: char = 'S';
+const GOAL: char = 'E';
+const MUD: char = '~';
+const ICE: char = '*';
+
+/// What stepping onto a mud cell costs, compared to 1 for a floor cell.
+const MUD_COST: isize = 4;
+/// What stepping onto an ice cell costs, slippery enough to be worth a detour.
+const ICE_COST: isize = 2;
+
+/// Obstacle course in the format of `maze.txt`.
+///
+/// Every cell is preceded by a separator column, `#` marks a wall, `S` the
+/// cell the snake starts on and `E` an optional goal. Searches pay more for
+/// stepping onto `~` mud, `*` ice or a digit from 2 to 9, which gives the
+/// cost of the cell directly.
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub width: usize,
+    pub height: usize,
+    pub start: Option<Position>,
+    pub goal: Option<Position>,
+    walls: Vec<Position>,
+    wall_grid: Vec<bool>,
+    costs: Vec<isize>,
+}
+
+impl Level {
+    /// A level without any walls which lets the snake start at its default
+    /// position.
+    pub fn empty(config: &BoardConfig) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            start: None,
+            goal: None,
+            walls: Vec::new(),
+            wall_grid: vec![false; config.cells()],
+            costs: vec![1; config.cells()],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read level `{}`", path.display()))?;
+        Self::parse(&text).with_context(|| format!("failed to parse level `{}`", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let rows = text
+            .lines()
+            .map(|line| line.trim_end().chars().skip(1).step_by(2).collect())
+            .collect::<Vec<Vec<char>>>();
+        let height = rows
+            .iter()
+            .rposition(|row| !row.is_empty())
+            .map_or(0, |i| i + 1);
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width < 2 || height < 2 {
+            bail!("a level needs at least 2x2 cells, got {}x{}", width, height);
+        }
+
+        let mut level = Self {
+            width,
+            height,
+            start: None,
+            goal: None,
+            walls: Vec::new(),
+            wall_grid: vec![false; width * height],
+            costs: vec![1; width * height],
+        };
+        for (y, row) in rows.iter().take(height).enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let pos = Position {
+                    x: x as isize,
+                    y: y as isize,
+                };
+                match *cell {
+                    WALL => {
+                        level.walls.push(pos);
+                        level.wall_grid[y * width + x] = true;
+                    }
+                    START if level.start.is_some() => {
+                        bail!("second `S` in row {}, column {}", y + 1, x + 1)
+                    }
+                    START => level.start = Some(pos),
+                    GOAL if level.goal.is_some() => {
+                        bail!("second `E` in row {}, column {}", y + 1, x + 1)
+                    }
+                    GOAL => level.goal = Some(pos),
+                    FLOOR => {}
+                    MUD => level.costs[y * width + x] = MUD_COST,
+                    ICE => level.costs[y * width + x] = ICE_COST,
+                    digit @ '2'..='9' => {
+                        level.costs[y * width + x] = digit as isize - '0' as isize;
+                    }
+                    other => bail!(
+                        "unknown cell `{}` in row {}, column {}",
+                        other,
+                        y + 1,
+                        x + 1
+                    ),
+                }
+            }
+        }
+        if level.start.is_none() {
+            bail!("the level has no start cell `S`");
+        }
+        if level.walls.len() + 1 == width * height {
+            bail!("the level has no free cell for an apple");
+        }
+        Ok(level)
+    }
+
+    /// Whether there is a wall at `pos`, cells off the level never are.
+    pub fn is_wall(&self, pos: Position) -> bool {
+        let (x, y) = (pos.x as usize, pos.y as usize);
+        if pos.x < 0 || pos.y < 0 || x >= self.width || y >= self.height {
+            return false;
+        }
+        self.wall_grid[y * self.width + x]
+    }
+
+    pub fn walls(&self) -> &[Position] {
+        &self.walls
+    }
+
+    /// What a search pays for stepping onto every cell, in row major order.
+    pub fn costs(&self) -> &[isize] {
+        &self.costs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Direction, GameState, StepOutcome};
+    use crate::snake::Snake;
+
+    /// Parses `rows` of cells, putting the separator column in between.
+    fn parse(rows: &[&str]) -> Result<Level> {
+        let text = rows
+            .iter()
+            .map(|row| row.chars().flat_map(|cell| [' ', cell]).collect::<String>() + "\n")
+            .collect::<String>();
+        Level::parse(&text)
+    }
+
+    fn error(rows: &[&str]) -> String {
+        parse(rows).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn maze_has_its_size_start_and_goal() {
+        let level = Level::load(Path::new("maze.txt")).unwrap();
+        assert_eq!((level.width, level.height), (55, 23));
+        assert_eq!(level.start, Some(Position { x: 52, y: 20 }));
+        assert_eq!(level.goal, Some(Position { x: 2, y: 2 }));
+        assert!(level.is_wall(Position { x: 0, y: 0 }));
+        assert!(!level.is_wall(Position { x: 52, y: 20 }));
+    }
+
+    #[test]
+    fn ragged_rows_are_padded_with_floor() {
+        let level = parse(&["S##", "#", ""]).unwrap();
+        assert_eq!((level.width, level.height), (3, 2));
+        let wall = |x, y| Position { x, y };
+        assert_eq!(level.walls(), &[wall(1, 0), wall(2, 0), wall(0, 1)]);
+        assert!(!level.is_wall(wall(2, 1)));
+        assert_eq!(level.costs(), &[1; 6]);
+    }
+
+    #[test]
+    fn walls_of_the_level_kill() {
+        let level = parse(&["S        #", "       #"]).unwrap();
+        let config = BoardConfig::new(level.width, level.height, 1);
+        let mut state = GameState::new(config, level, 0);
+        state.snake = Snake {
+            tail: (0..7).rev().map(|x| Position { x, y: 1 }).collect(),
+            direction: Some(Direction::Right),
+        };
+        assert!(state.level.is_wall(Position { x: 7, y: 1 }));
+        assert_eq!(state.step(None), StepOutcome::Died);
+    }
+
+    #[test]
+    fn broken_levels_are_rejected() {
+        assert_eq!(error(&["#E", "##"]), "the level has no start cell `S`");
+        assert_eq!(error(&["S ", " x"]), "unknown cell `x` in row 2, column 2");
+        assert_eq!(error(&["S~", "S "]), "second `S` in row 2, column 1");
+        assert_eq!(error(&["S"]), "a level needs at least 2x2 cells, got 1x1");
+        assert_eq!(
+            error(&["S#", "##"]),
+            "the level has no free cell for an apple"
+        );
+    }
+}
@@ -0,0 +1,26 @@
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![allow(clippy::cast_precision_loss)]
+#![allow(clippy::cast_possible_wrap)]
+#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::cast_sign_loss)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::return_self_not_must_use)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_panics_doc)]
+#![feature(deque_range)]
+#![feature(test)]
+
+//! The rules of the game and every computer player, free of any window or
+//! renderer so they can run headless.
+
+pub mod board;
+pub mod engine;
+pub mod headless;
+pub mod level;
+pub mod neighbourhood;
+pub mod options;
+pub mod pathfinding;
+pub mod search;
+pub mod snake;
+pub mod strategy;
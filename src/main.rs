@@ -4,29 +4,43 @@
 #![allow(clippy::cast_possible_wrap)]
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_sign_loss)]
-#![feature(deque_range)]
-#![feature(test)]
-#![feature(map_first_last)]
 
-use crate::game::{SnakeGame, WINDOW_SIZE_X, WINDOW_SIZE_Y};
 use anyhow::Result;
-use coffee::graphics::WindowSettings;
-use coffee::ui::UserInterface;
+use bake::headless;
+use bake::options::Options;
 
+#[cfg(feature = "gui")]
 mod game;
-mod search;
-mod snake;
 
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 fn main() -> Result<()> {
+    let options = Options::from_args()?.install();
+    if let Some(games) = options.headless {
+        return headless::run(options, games);
+    }
+    run_window(options)
+}
+
+#[cfg(feature = "gui")]
+fn run_window(options: &Options) -> Result<()> {
+    use crate::game::SnakeGame;
+    use coffee::graphics::WindowSettings;
+    use coffee::ui::UserInterface;
+
+    let board = options.board;
     <SnakeGame as UserInterface>::run(WindowSettings {
         title: String::from("A baked snake"),
-        size: (WINDOW_SIZE_X as u32, WINDOW_SIZE_Y as u32),
+        size: (board.width_px() as u32, board.height_px() as u32),
         resizable: false,
         fullscreen: false,
         maximized: false,
     })?;
     Ok(())
 }
+
+#[cfg(not(feature = "gui"))]
+fn run_window(_options: &Options) -> Result<()> {
+    anyhow::bail!("built without the `gui` feature, only `--headless` games can run")
+}
@@ -0,0 +1,177 @@
+use crate::board::{BoardConfig, BoundaryRule};
+use crate::snake::Position;
+
+/// Cost of a straight step with `DiagonalCost::Octile`, in tenths of a cell.
+pub const STRAIGHT_COST: isize = 10;
+/// Cost of a diagonal step with `DiagonalCost::Octile`, about `STRAIGHT_COST`
+/// times the square root of two.
+pub const DIAGONAL_COST: isize = 14;
+
+const FOUR: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+const EIGHT: [(isize, isize); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+const HEX_EVEN_ROW: [(isize, isize); 6] = [(-1, 0), (1, 0), (-1, -1), (0, -1), (-1, 1), (0, 1)];
+const HEX_ODD_ROW: [(isize, isize); 6] = [(-1, 0), (1, 0), (0, -1), (1, -1), (0, 1), (1, 1)];
+
+/// The cells a search may step to from any given cell. The snake itself
+/// always moves in the `Four` neighbourhood, the others are there for mazes.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum Neighbourhood {
+    /// The cells sharing an edge, one step costs 1.
+    #[default]
+    Four,
+    /// The cells sharing an edge or a corner.
+    Eight {
+        diagonal: DiagonalCost,
+        corners: Corners,
+    },
+    /// The six cells around a hexagon, one step costs 1. The board is read as
+    /// a hex grid whose odd rows are shifted half a cell to the right, which
+    /// only wraps around cleanly if the board has an even number of rows.
+    Hex,
+}
+
+/// What a step in `Neighbourhood::Eight` costs.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DiagonalCost {
+    /// Every step costs 1, the heuristic is the Chebyshev distance.
+    Uniform,
+    /// Straight steps cost `STRAIGHT_COST` and diagonal ones `DIAGONAL_COST`,
+    /// the heuristic is the octile distance.
+    Octile,
+}
+
+/// When a diagonal step may pass the two cells it cuts between.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Corners {
+    /// Always, even squeezing between two blocked cells.
+    Cut,
+    /// Unless both of them are blocked.
+    Touch,
+    /// Only if both of them are free.
+    Avoid,
+}
+
+/// Every neighbourhood by the name accepted by `--neighbourhood`.
+pub const NEIGHBOURHOODS: [(&str, Neighbourhood); 4] = [
+    ("four", Neighbourhood::Four),
+    (
+        "eight",
+        Neighbourhood::Eight {
+            diagonal: DiagonalCost::Octile,
+            corners: Corners::Avoid,
+        },
+    ),
+    (
+        "eight-uniform",
+        Neighbourhood::Eight {
+            diagonal: DiagonalCost::Uniform,
+            corners: Corners::Avoid,
+        },
+    ),
+    ("hex", Neighbourhood::Hex),
+];
+
+/// Every corner rule by the name accepted by `--corners`.
+pub const CORNERS: [(&str, Corners); 3] = [
+    ("cut", Corners::Cut),
+    ("touch", Corners::Touch),
+    ("avoid", Corners::Avoid),
+];
+
+/// The neighbourhood called `name` in `NEIGHBOURHOODS`.
+pub fn find(name: &str) -> Option<Neighbourhood> {
+    NEIGHBOURHOODS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, neighbourhood)| *neighbourhood)
+}
+
+/// The corner rule called `name` in `CORNERS`.
+pub fn find_corners(name: &str) -> Option<Corners> {
+    CORNERS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, corners)| *corners)
+}
+
+impl Neighbourhood {
+    /// Offsets from `pos` to its neighbours, before wrapping.
+    pub const fn offsets(self, pos: Position) -> &'static [(isize, isize)] {
+        match self {
+            Self::Four => &FOUR,
+            Self::Eight { .. } => &EIGHT,
+            Self::Hex if pos.y & 1 == 0 => &HEX_EVEN_ROW,
+            Self::Hex => &HEX_ODD_ROW,
+        }
+    }
+
+    /// Cost of the step by `offset`, one of `offsets`.
+    pub const fn step_cost(self, offset: (isize, isize)) -> isize {
+        match self {
+            Self::Eight {
+                diagonal: DiagonalCost::Octile,
+                ..
+            } if offset.0 != 0 && offset.1 != 0 => DIAGONAL_COST,
+            Self::Eight {
+                diagonal: DiagonalCost::Octile,
+                ..
+            } => STRAIGHT_COST,
+            _ => 1,
+        }
+    }
+
+    /// The rule for diagonal steps, `None` if there are none.
+    pub const fn corners(self) -> Option<Corners> {
+        match self {
+            Self::Eight { corners, .. } => Some(corners),
+            Self::Four | Self::Hex => None,
+        }
+    }
+
+    /// Cost of the cheapest walk between `a` and `b` on an empty board,
+    /// taking the way across the edges into account when they wrap around.
+    pub fn distance(self, config: &BoardConfig, a: Position, b: Position) -> isize {
+        if self == Self::Four {
+            return config.distance(a, b);
+        }
+        let (width, height) = (config.width as isize, config.height as isize);
+        let shifts: &[isize] = match config.boundary {
+            BoundaryRule::Walls => &[0],
+            BoundaryRule::Wrap => &[-1, 0, 1],
+        };
+        let copies = shifts.iter().flat_map(|sx| {
+            shifts.iter().map(move |sy| Position {
+                x: b.x + sx * width,
+                y: b.y + sy * height,
+            })
+        });
+        copies
+            .map(|b| self.plain_distance(a, b))
+            .min()
+            .unwrap_or_default()
+    }
+
+    const fn plain_distance(self, a: Position, b: Position) -> isize {
+        match self {
+            Self::Four => a.mhtn_dist(b),
+            Self::Eight {
+                diagonal: DiagonalCost::Uniform,
+                ..
+            } => a.chebyshev_dist(b),
+            Self::Eight {
+                diagonal: DiagonalCost::Octile,
+                ..
+            } => a.octile_dist(b, STRAIGHT_COST, DIAGONAL_COST),
+            Self::Hex => a.hex_dist(b),
+        }
+    }
+}
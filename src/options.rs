@@ -0,0 +1,158 @@
+use crate::board::{BoardConfig, BoundaryRule};
+use crate::level::Level;
+use crate::neighbourhood::{self, Neighbourhood};
+use crate::{pathfinding, strategy};
+use anyhow::{anyhow, bail, Context, Result};
+use rand::Rng;
+use std::path::Path;
+use std::sync::OnceLock;
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+/// Level used by `--maze` unless another one is given with `--level`.
+const DEFAULT_MAZE: &str = "maze.txt";
+
+/// Settings chosen on the command line before the window is opened.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub board: BoardConfig,
+    pub seed: Option<u64>,
+    pub level: Option<Level>,
+    pub maze: bool,
+    /// Index into `strategy::STRATEGIES` of the computer player.
+    pub strategy: usize,
+    /// Index into `pathfinding::PATH_FINDERS` of the search solving mazes.
+    pub finder: usize,
+    /// Cells the maze search may step to from any given cell.
+    pub neighbourhood: Neighbourhood,
+    /// Number of games to simulate without opening a window.
+    pub headless: Option<usize>,
+}
+
+impl Options {
+    pub fn from_args() -> Result<Self> {
+        let mut options = Self::default();
+        let mut corners = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("missing value for `{}`", arg))
+            };
+            match arg.as_str() {
+                "--board" => options.board = parse_board(&value()?, options.board)?,
+                "--cell-px" => {
+                    options.board.cell_px = value()?.parse().context("invalid `--cell-px`")?;
+                }
+                "--wrap" => options.board.boundary = BoundaryRule::Wrap,
+                "--seed" => options.seed = Some(value()?.parse().context("invalid `--seed`")?),
+                "--level" => options.level = Some(Level::load(Path::new(&value()?))?),
+                "--maze" => options.maze = true,
+                "--strategy" => {
+                    let name = value()?;
+                    options.strategy = strategy::find(&name).ok_or_else(|| {
+                        let known = strategy::STRATEGIES.map(|(name, _)| name).join(", ");
+                        anyhow!("unknown strategy `{}`, expected one of {}", name, known)
+                    })?;
+                }
+                "--finder" => {
+                    let name = value()?;
+                    options.finder = pathfinding::find(&name).ok_or_else(|| {
+                        let known = pathfinding::PATH_FINDERS.map(|(name, _)| name).join(", ");
+                        anyhow!("unknown path finder `{}`, expected one of {}", name, known)
+                    })?;
+                }
+                "--neighbourhood" => {
+                    let name = value()?;
+                    options.neighbourhood = neighbourhood::find(&name).ok_or_else(|| {
+                        let known = neighbourhood::NEIGHBOURHOODS
+                            .map(|(name, _)| name)
+                            .join(", ");
+                        anyhow!(
+                            "unknown neighbourhood `{}`, expected one of {}",
+                            name,
+                            known
+                        )
+                    })?;
+                }
+                "--corners" => {
+                    let name = value()?;
+                    corners = Some(neighbourhood::find_corners(&name).ok_or_else(|| {
+                        let known = neighbourhood::CORNERS.map(|(name, _)| name).join(", ");
+                        anyhow!("unknown corner rule `{}`, expected one of {}", name, known)
+                    })?);
+                }
+                "--headless" => {
+                    options.headless = Some(value()?.parse().context("invalid `--headless`")?);
+                }
+                _ => bail!("unknown argument `{}`", arg),
+            }
+        }
+        if options.board.cell_px == 0 {
+            bail!("`--cell-px` must be at least 1");
+        }
+        if options.maze && options.level.is_none() {
+            options.level = Some(Level::load(Path::new(DEFAULT_MAZE))?);
+        }
+        if let Some(level) = &options.level {
+            options.board.width = level.width;
+            options.board.height = level.height;
+        }
+        if let Some(corners) = corners {
+            match &mut options.neighbourhood {
+                Neighbourhood::Eight { corners: rule, .. } => *rule = corners,
+                _ => bail!("`--corners` needs a neighbourhood with diagonal steps"),
+            }
+        }
+        if options.neighbourhood == Neighbourhood::Hex
+            && options.board.boundary == BoundaryRule::Wrap
+            && options.board.height % 2 == 1
+        {
+            bail!("a wrapping hex grid needs an even number of rows");
+        }
+        Ok(options)
+    }
+
+    /// Makes `self` available to `SnakeGame::load`, which coffee calls without
+    /// giving us a chance to pass arguments along.
+    pub fn install(self) -> &'static Self {
+        OPTIONS.get_or_init(|| self)
+    }
+
+    pub fn get() -> &'static Self {
+        OPTIONS.get_or_init(Self::default)
+    }
+
+    /// The level given with `--level` or an empty board.
+    pub fn level(&self) -> Level {
+        self.level
+            .clone()
+            .unwrap_or_else(|| Level::empty(&self.board))
+    }
+
+    /// The seed given with `--seed` or a fresh random one.
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| rand::thread_rng().gen())
+    }
+}
+
+/// Parses board dimensions in the form `WIDTHxHEIGHT`, e.g. `17x31`.
+fn parse_board(value: &str, current: BoardConfig) -> Result<BoardConfig> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| anyhow!("expected `WIDTHxHEIGHT` for `--board`, got `{}`", value))?;
+    let width: usize = width.parse().context("invalid board width")?;
+    let height: usize = height.parse().context("invalid board height")?;
+    if width < 2 || height < 2 {
+        bail!(
+            "the board needs at least 2x2 cells, got {}x{}",
+            width,
+            height
+        );
+    }
+    Ok(BoardConfig {
+        width,
+        height,
+        ..current
+    })
+}
@@ -0,0 +1,551 @@
+use crate::search::{Path, Priority, SearchStats, Searcher};
+use crate::snake::{Position, Tail};
+use hashbrown::HashMap;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+/// A way of finding a path between two cells. All of them share the board,
+/// walls and obstacle cache of the `Searcher` they are built from.
+pub trait PathFinder: Debug {
+    /// A path from `start` to `goal` that avoids the walls and `obstacles`,
+    /// the shortest one unless the finder says otherwise.
+    fn find_path(&mut self, start: Position, goal: Position, obstacles: &Tail) -> Option<Path>;
+
+    /// Work done by the last `find_path`.
+    fn stats(&self) -> &SearchStats;
+}
+
+type Constructor = fn(Searcher) -> Box<dyn PathFinder>;
+
+/// Every path finder by the name accepted by `--finder`.
+pub const PATH_FINDERS: [(&str, Constructor); 6] = [
+    ("a-star", |searcher| Box::new(searcher)),
+    ("dijkstra", |searcher| Box::new(Dijkstra(searcher))),
+    ("best-first", |searcher| Box::new(BestFirst(searcher))),
+    ("bfs", |searcher| Box::new(Bfs(searcher))),
+    ("bidirectional", |searcher| {
+        Box::new(Bidirectional(searcher))
+    }),
+    ("ida-star", |searcher| Box::new(IdaStar(searcher))),
+];
+
+/// Index of the path finder called `name` in `PATH_FINDERS`.
+pub fn find(name: &str) -> Option<usize> {
+    PATH_FINDERS.iter().position(|(n, _)| *n == name)
+}
+
+pub fn create(index: usize, searcher: Searcher) -> Box<dyn PathFinder> {
+    (PATH_FINDERS[index].1)(searcher)
+}
+
+impl PathFinder for Searcher {
+    fn find_path(&mut self, start: Position, goal: Position, obstacles: &Tail) -> Option<Path> {
+        self.a_star(start, goal, obstacles)
+    }
+
+    fn stats(&self) -> &SearchStats {
+        Self::stats(self)
+    }
+}
+
+/// Expands the cheapest node first without looking at the goal.
+#[derive(Debug)]
+pub struct Dijkstra(pub Searcher);
+
+impl PathFinder for Dijkstra {
+    fn find_path(&mut self, start: Position, goal: Position, obstacles: &Tail) -> Option<Path> {
+        self.0.search_by(start, goal, obstacles, Priority::Cost)
+    }
+
+    fn stats(&self) -> &SearchStats {
+        self.0.stats()
+    }
+}
+
+/// Expands the node closest to the goal first. The path can be longer than
+/// necessary.
+#[derive(Debug)]
+pub struct BestFirst(pub Searcher);
+
+impl PathFinder for BestFirst {
+    fn find_path(&mut self, start: Position, goal: Position, obstacles: &Tail) -> Option<Path> {
+        self.0
+            .search_by(start, goal, obstacles, Priority::Heuristic)
+    }
+
+    fn stats(&self) -> &SearchStats {
+        self.0.stats()
+    }
+}
+
+/// Breadth-first search, the path has the fewest moves but is not the
+/// cheapest one if some steps cost more than others.
+#[derive(Debug)]
+pub struct Bfs(pub Searcher);
+
+impl PathFinder for Bfs {
+    fn find_path(&mut self, start: Position, goal: Position, obstacles: &Tail) -> Option<Path> {
+        self.0.measure(|searcher| {
+            searcher.reset();
+            searcher.update_obs_cache(obstacles);
+
+            let mut parents = HashMap::new();
+            parents.insert(start, (start, 0));
+            let mut queue = VecDeque::new();
+            queue.push_back(start);
+            searcher.stats_mut().generated += 1;
+            while let Some(pos) = queue.pop_front() {
+                let stats = searcher.stats_mut();
+                stats.expanded += 1;
+                stats.track(queue.len() + 1, parents.len() - queue.len());
+                if pos == goal {
+                    return Some(walk_back(&parents, start, goal));
+                }
+                let (cost, queued) = (parents[&pos].1, queue.len());
+                for (next, step_cost) in searcher.neighbours(pos) {
+                    if !searcher.is_blocked(next) && !parents.contains_key(&next) {
+                        parents.insert(next, (pos, cost + step_cost));
+                        queue.push_back(next);
+                    }
+                }
+                searcher.stats_mut().generated += queue.len() - queued;
+            }
+            None
+        })
+    }
+
+    fn stats(&self) -> &SearchStats {
+        self.0.stats()
+    }
+}
+
+/// Breadth-first search from both ends at once, always growing the smaller
+/// frontier by a whole layer until the two meet. Like `Bfs` it finds the path
+/// with the fewest moves.
+#[derive(Debug)]
+pub struct Bidirectional(pub Searcher);
+
+impl PathFinder for Bidirectional {
+    fn find_path(&mut self, start: Position, goal: Position, obstacles: &Tail) -> Option<Path> {
+        self.0
+            .measure(|searcher| bidirectional(searcher, start, goal, obstacles))
+    }
+
+    fn stats(&self) -> &SearchStats {
+        self.0.stats()
+    }
+}
+
+/// The search of `Bidirectional`, measured as a whole.
+fn bidirectional(
+    searcher: &mut Searcher,
+    start: Position,
+    goal: Position,
+    obstacles: &Tail,
+) -> Option<Path> {
+    searcher.reset();
+    searcher.update_obs_cache(obstacles);
+    searcher.stats_mut().generated += 2;
+
+    // Parent and cost from the end the side started at.
+    let mut forward = HashMap::new();
+    let mut backward = HashMap::new();
+    forward.insert(start, (start, 0));
+    backward.insert(goal, (goal, 0));
+    let (mut forward_layer, mut backward_layer) = (vec![start], vec![goal]);
+    let mut meeting = (start == goal).then_some(start);
+
+    while meeting.is_none() && !forward_layer.is_empty() && !backward_layer.is_empty() {
+        let expand_forward = forward_layer.len() <= backward_layer.len();
+        let (layer, seen, other) = if expand_forward {
+            (&mut forward_layer, &mut forward, &backward)
+        } else {
+            (&mut backward_layer, &mut backward, &forward)
+        };
+
+        let mut next_layer = Vec::new();
+        let mut best = None;
+        searcher.stats_mut().expanded += layer.len();
+        for pos in layer.drain(..) {
+            let cost = seen[&pos].1;
+            for (next, step_cost) in searcher.neighbours(pos) {
+                if searcher.is_blocked(next) || seen.contains_key(&next) {
+                    continue;
+                }
+                // The way back to the goal steps from `next` onto `pos`,
+                // which only differs in the terrain entered.
+                let step_cost = if expand_forward {
+                    step_cost
+                } else {
+                    step_cost / searcher.terrain(next) * searcher.terrain(pos)
+                };
+                let cost = cost + step_cost;
+                seen.insert(next, (pos, cost));
+                next_layer.push(next);
+                if let Some((_, rest)) = other.get(&next) {
+                    if best.is_none_or(|(_, total)| cost + rest < total) {
+                        best = Some((next, cost + rest));
+                    }
+                }
+            }
+        }
+        searcher.stats_mut().generated += next_layer.len();
+        *layer = next_layer;
+        meeting = best.map(|(pos, _)| pos);
+
+        let open = forward_layer.len() + backward_layer.len();
+        let seen = forward.len() + backward.len();
+        searcher.stats_mut().track(open, seen - open);
+    }
+
+    let meeting = meeting?;
+    let mut path = walk_back(&forward, start, meeting);
+    let mut pos = meeting;
+    while pos != goal {
+        pos = backward[&pos].0;
+        path.cells.push(pos);
+    }
+    path.cost += backward[&meeting].1;
+    Some(path)
+}
+
+/// Iterative deepening A*.
+///
+/// A depth-first search that gives up on every path whose estimated cost
+/// exceeds a bound and raises the bound until the goal is reached. Cells
+/// reached at no lower cost than before in the same round are skipped, which
+/// keeps it from exploring the same area over and over.
+#[derive(Debug)]
+pub struct IdaStar(pub Searcher);
+
+impl PathFinder for IdaStar {
+    fn find_path(&mut self, start: Position, goal: Position, obstacles: &Tail) -> Option<Path> {
+        self.0.measure(|searcher| {
+            searcher.reset();
+            searcher.update_obs_cache(obstacles);
+
+            let mut bound = searcher.distance(start, goal);
+            let mut cells = vec![start];
+            let mut best = HashMap::new();
+            let mut stats = SearchStats::default();
+            let path = loop {
+                best.clear();
+                best.insert(start, 0);
+                stats.generated += 1;
+                match probe(searcher, &mut cells, 0, bound, goal, &mut best, &mut stats) {
+                    Ok(cost) => break Some(Path { cells, cost }),
+                    Err(Some(next_bound)) => bound = next_bound,
+                    Err(None) => break None,
+                }
+            };
+            searcher.stats_mut().add(&stats);
+            path
+        })
+    }
+
+    fn stats(&self) -> &SearchStats {
+        self.0.stats()
+    }
+}
+
+/// Depth-first step of `IdaStar` from the last of `cells`, which was reached
+/// at `cost`. Returns the cost of the path to `goal` left in `cells` or the
+/// lowest estimate that exceeded `bound`, `None` if nothing did. The open set
+/// of `stats` is the path being probed, the closed one the cells in `best`.
+fn probe(
+    searcher: &Searcher,
+    cells: &mut Vec<Position>,
+    cost: isize,
+    bound: isize,
+    goal: Position,
+    best: &mut HashMap<Position, isize>,
+    stats: &mut SearchStats,
+) -> Result<isize, Option<isize>> {
+    let pos = cells[cells.len() - 1];
+    let estimate = cost + searcher.distance(pos, goal);
+    if estimate > bound {
+        return Err(Some(estimate));
+    }
+    if pos == goal {
+        return Ok(cost);
+    }
+    stats.expanded += 1;
+    stats.track(cells.len(), best.len());
+
+    let mut next_bound = None;
+    for (next, step_cost) in searcher.neighbours(pos) {
+        let next_cost = cost + step_cost;
+        if searcher.is_blocked(next) || matches!(best.get(&next), Some(c) if *c <= next_cost) {
+            continue;
+        }
+        best.insert(next, next_cost);
+        cells.push(next);
+        stats.generated += 1;
+        match probe(searcher, cells, next_cost, bound, goal, best, stats) {
+            Ok(cost) => return Ok(cost),
+            Err(Some(exceeded)) => {
+                next_bound = Some(next_bound.map_or(exceeded, |b: isize| b.min(exceeded)));
+            }
+            Err(None) => {}
+        }
+        cells.pop();
+    }
+    Err(next_bound)
+}
+
+/// The path from `start` to `goal` along the parents a search left behind,
+/// next to the cost of reaching every cell. `start` is its own parent.
+fn walk_back(
+    parents: &HashMap<Position, (Position, isize)>,
+    start: Position,
+    goal: Position,
+) -> Path {
+    let mut cells = vec![goal];
+    let mut pos = goal;
+    while pos != start {
+        pos = parents[&pos].0;
+        cells.push(pos);
+    }
+    cells.reverse();
+    Path {
+        cells,
+        cost: parents[&goal].1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{BoardConfig, BoundaryRule};
+    use crate::level::Level;
+    use crate::neighbourhood::{
+        Corners, DiagonalCost, Neighbourhood, DIAGONAL_COST, STRAIGHT_COST,
+    };
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    use std::path::Path as FilePath;
+
+    /// Finders that always find the cheapest path.
+    const OPTIMAL: [&str; 3] = ["a-star", "dijkstra", "ida-star"];
+    /// Finders that find the path with the fewest moves, which is also the
+    /// cheapest one while every step costs 1.
+    const FEWEST_MOVES: [&str; 2] = ["bfs", "bidirectional"];
+
+    const NEIGHBOURHOODS: [Neighbourhood; 6] = [
+        Neighbourhood::Four,
+        Neighbourhood::Eight {
+            diagonal: DiagonalCost::Octile,
+            corners: Corners::Cut,
+        },
+        Neighbourhood::Eight {
+            diagonal: DiagonalCost::Octile,
+            corners: Corners::Touch,
+        },
+        Neighbourhood::Eight {
+            diagonal: DiagonalCost::Octile,
+            corners: Corners::Avoid,
+        },
+        Neighbourhood::Eight {
+            diagonal: DiagonalCost::Uniform,
+            corners: Corners::Avoid,
+        },
+        Neighbourhood::Hex,
+    ];
+
+    /// Everything the searcher behind a finder is set up with.
+    #[derive(Debug, Clone)]
+    struct Grid {
+        config: BoardConfig,
+        walls: Vec<Position>,
+        costs: Vec<isize>,
+        neighbourhood: Neighbourhood,
+    }
+
+    impl Grid {
+        fn new(config: BoardConfig, walls: &[Position], neighbourhood: Neighbourhood) -> Self {
+            Self {
+                config,
+                walls: walls.to_vec(),
+                costs: vec![1; config.cells()],
+                neighbourhood,
+            }
+        }
+
+        fn load(path: &str, neighbourhood: Neighbourhood) -> (Self, Position, Position) {
+            let level = Level::load(FilePath::new(path)).unwrap();
+            let config = BoardConfig::new(level.width, level.height, 1);
+            let mut grid = Self::new(config, level.walls(), neighbourhood);
+            grid.costs = level.costs().to_vec();
+            (grid, level.start.unwrap(), level.goal.unwrap())
+        }
+
+        fn searcher(&self) -> Searcher {
+            let mut searcher = Searcher::new(self.config);
+            searcher.set_walls(&self.walls);
+            searcher.set_terrain(&self.costs);
+            searcher.set_neighbourhood(self.neighbourhood);
+            searcher
+        }
+
+        fn finder(&self, name: &str) -> Box<dyn PathFinder> {
+            create(find(name).unwrap(), self.searcher())
+        }
+
+        fn find_path(&self, name: &str, start: Position, goal: Position) -> Option<Path> {
+            self.finder(name).find_path(start, goal, &Tail::new())
+        }
+
+        /// Whether every step costs 1, so the fewest moves are also the
+        /// cheapest.
+        fn unit_steps(&self) -> bool {
+            self.neighbourhood.step_cost((1, 1)) == 1 && self.costs.iter().all(|cost| *cost == 1)
+        }
+    }
+
+    /// Checks that `path` is a walk from `start` to `goal` around the walls
+    /// that costs what it says.
+    fn assert_walk(grid: &Grid, path: &Path, start: Position, goal: Position) {
+        let mut searcher = grid.searcher();
+        searcher.update_obs_cache(&Tail::new());
+        assert_eq!(path.cells.first(), Some(&start));
+        assert_eq!(path.cells.last(), Some(&goal));
+        let mut cost = 0;
+        for pair in path.cells.windows(2) {
+            let step = searcher
+                .neighbours(pair[0])
+                .find(|(pos, _)| *pos == pair[1]);
+            let (_, step_cost) = step.unwrap_or_else(|| panic!("{:?} is not a step", pair));
+            assert!(
+                !grid.walls.contains(&pair[1]),
+                "the path enters the wall {:?}",
+                pair[1]
+            );
+            cost += step_cost;
+        }
+        assert_eq!(path.cost, cost);
+    }
+
+    fn assert_finders_agree(grid: &Grid, start: Position, goal: Position) {
+        let cost_and_len = |path: Option<Path>| path.map(|path| (path.cost, path.cells.len()));
+        let cheapest = cost_and_len(grid.find_path("dijkstra", start, goal));
+        let fewest_moves = cost_and_len(grid.find_path("bfs", start, goal));
+        for (name, _) in &PATH_FINDERS {
+            let path = grid.find_path(name, start, goal);
+            if let Some(path) = &path {
+                assert_walk(grid, path, start, goal);
+            }
+            let found = cost_and_len(path);
+            let context = format!("{name} on {grid:?} from {start:?} to {goal:?}");
+            if OPTIMAL.contains(name) || grid.unit_steps() && FEWEST_MOVES.contains(name) {
+                assert_eq!(
+                    found.map(|(cost, _)| cost),
+                    cheapest.map(|(cost, _)| cost),
+                    "{context}"
+                );
+            } else if FEWEST_MOVES.contains(name) {
+                assert_eq!(
+                    found.map(|(_, len)| len),
+                    fewest_moves.map(|(_, len)| len),
+                    "{context}"
+                );
+            } else {
+                assert_eq!(found.is_some(), cheapest.is_some(), "{context}");
+            }
+        }
+    }
+
+    #[test]
+    fn finders_solve_the_maze() {
+        // IDA* takes a round for every bound it tries, far too many on a
+        // maze this size once steps cost different amounts.
+        for neighbourhood in &NEIGHBOURHOODS {
+            if neighbourhood.step_cost((1, 1)) == 1 {
+                let (grid, start, goal) = Grid::load("maze.txt", *neighbourhood);
+                assert_finders_agree(&grid, start, goal);
+            }
+        }
+    }
+
+    #[test]
+    fn finders_agree_on_random_grids() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let mut config = BoardConfig::new(rng.gen_range(2, 16), rng.gen_range(2, 16), 1);
+            if rng.gen() {
+                config.boundary = BoundaryRule::Wrap;
+            }
+            let cells = (0..config.cells()).map(|i| config.position(i));
+            let (walls, free): (Vec<_>, Vec<_>) = cells.partition(|_| rng.gen_bool(0.3));
+            let neighbourhood = *NEIGHBOURHOODS.choose(&mut rng).unwrap();
+            if neighbourhood == Neighbourhood::Hex
+                && config.boundary == BoundaryRule::Wrap
+                && config.height % 2 == 1
+            {
+                continue;
+            }
+            let mut grid = Grid::new(config, &walls, neighbourhood);
+            if rng.gen() {
+                grid.costs = (0..config.cells()).map(|_| rng.gen_range(1, 5)).collect();
+            }
+            if let (Some(start), Some(goal)) = (free.choose(&mut rng), free.choose(&mut rng)) {
+                assert_finders_agree(&grid, *start, *goal);
+            }
+        }
+    }
+
+    #[test]
+    fn distance_is_exact_on_empty_boards() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..200 {
+            let height = rng.gen_range(1, 8) * 2;
+            let mut config = BoardConfig::new(rng.gen_range(2, 16), height, 1);
+            if rng.gen() {
+                config.boundary = BoundaryRule::Wrap;
+            }
+            let grid = Grid::new(config, &[], *NEIGHBOURHOODS.choose(&mut rng).unwrap());
+            let start = config.position(rng.gen_range(0, config.cells()));
+            let goal = config.position(rng.gen_range(0, config.cells()));
+            let path = grid.find_path("dijkstra", start, goal).unwrap();
+            assert_eq!(
+                grid.searcher().distance(start, goal),
+                path.cost,
+                "{grid:?} from {start:?} to {goal:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn diagonal_steps_follow_the_corner_rule() {
+        let config = BoardConfig::new(2, 2, 1);
+        let (start, goal) = (Position { x: 0, y: 0 }, Position { x: 1, y: 1 });
+        let cost = |corners, walls: &[Position]| {
+            let neighbourhood = Neighbourhood::Eight {
+                diagonal: DiagonalCost::Octile,
+                corners,
+            };
+            Grid::new(config, walls, neighbourhood)
+                .find_path("a-star", start, goal)
+                .map(|path| path.cost)
+        };
+        let one_side = [Position { x: 1, y: 0 }];
+        let both_sides = [Position { x: 1, y: 0 }, Position { x: 0, y: 1 }];
+
+        assert_eq!(cost(Corners::Cut, &both_sides), Some(DIAGONAL_COST));
+        assert_eq!(cost(Corners::Touch, &both_sides), None);
+        assert_eq!(cost(Corners::Touch, &one_side), Some(DIAGONAL_COST));
+        assert_eq!(cost(Corners::Avoid, &one_side), Some(2 * STRAIGHT_COST));
+        assert_eq!(cost(Corners::Avoid, &[]), Some(DIAGONAL_COST));
+    }
+
+    #[test]
+    fn cheapest_path_goes_around_the_swamp() {
+        let (grid, start, goal) = Grid::load("swamp.txt", Neighbourhood::Four);
+        assert_finders_agree(&grid, start, goal);
+
+        let shortest = grid.find_path("bfs", start, goal).unwrap();
+        let cheapest = grid.find_path("a-star", start, goal).unwrap();
+        assert_eq!((shortest.cells.len() - 1, shortest.cost), (12, 21));
+        assert_eq!((cheapest.cells.len() - 1, cheapest.cost), (20, 20));
+    }
+}
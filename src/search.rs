@@ -1,168 +1,843 @@
-use crate::game::*;
-use crate::game::{WINDOW_SIZE_X, WINDOW_SIZE_Y};
-use crate::snake::*;
+use crate::board::BoardConfig;
+use crate::engine::{Direction, GameView};
+use crate::level::Level;
+use crate::neighbourhood::{Corners, Neighbourhood};
+use crate::snake::{Position, Snake};
+use anyhow::{bail, Result};
 use hashbrown::HashSet;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use std::cmp::Ordering;
-use std::collections::{BTreeSet, VecDeque};
-use std::hash::{Hash, Hasher};
-
-const NODE_LEN_X: usize = WINDOW_SIZE_X / NODE_SIZE;
-const NODE_LEN_Y: usize = WINDOW_SIZE_Y / NODE_SIZE;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
 
 type Board = Vec<Vec<Position>>;
 
-const MAX_NODE_LINK_LEN: usize = (WINDOW_SIZE_X / NODE_SIZE) * (WINDOW_SIZE_Y / NODE_SIZE);
+/// Number of free cells a shortcut has to leave between the head and the
+/// closest body node ahead of it on the cycle, so growing can't close the gap.
+const SHORTCUT_BUFFER: usize = 3;
+
+/// The family of Hamiltonian cycles a `Solver` follows.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CycleKind {
+    /// The fixed cycle of `Solver::gen_zig_zag_path`.
+    ZigZag,
+    /// A random cycle traced around a random spanning tree, see
+    /// `Solver::gen_spanning_tree_path`.
+    SpanningTree { seed: u64 },
+}
 
 #[derive(Debug)]
 pub struct Solver {
+    config: BoardConfig,
     pub board: Board,
     pub searcher: Searcher,
-    cursor: usize,
     pub path: Vec<Position>,
+    /// Position of every cell in `path` by `BoardConfig::index`. It has to be
+    /// rebuilt with `index_cycle` whenever `path` changes.
+    cycle_index: Vec<Option<usize>>,
+    /// Positions in `path` of the neighbours of every cell of `path`, in the
+    /// order of `Direction::ALL`. Rebuilt along with `cycle_index`.
+    cycle_neighbours: Vec<[Option<usize>; 4]>,
+    /// Whether `repair_cycle` reshapes `path` before every move.
+    pub repair: bool,
+    /// Head and apple after the last move of `make_move` while on track.
+    /// Following the cycle keeps the snake on track until a new apple shows
+    /// up, so `is_on_track` needn't look at the body again until then.
+    track: Option<(Position, Position)>,
+    /// Head, first body node ahead of it and apple when `best_splice` last
+    /// came up empty, see `repair_cycle`.
+    checked: Option<(usize, usize, Position)>,
 }
 
 impl Solver {
-    pub fn new() -> Self {
-        Self {
-            // TODO(Simon): I might have messed up the order of (x, y)
-            board: vec![vec![Position::default(); NODE_LEN_X]; NODE_LEN_Y],
-            path: Vec::with_capacity(MAX_NODE_LINK_LEN),
-            searcher: Searcher::new(),
-            cursor: 0,
+    pub fn new(config: BoardConfig, cycle: CycleKind) -> Result<Self> {
+        let mut solver = Self {
+            config,
+            board: vec![vec![Position::default(); config.height]; config.width],
+            path: Vec::with_capacity(config.cells()),
+            cycle_index: vec![None; config.cells()],
+            cycle_neighbours: Vec::with_capacity(config.cells()),
+            searcher: Searcher::new(config),
+            repair: false,
+            track: None,
+            checked: None,
+        };
+        match cycle {
+            CycleKind::ZigZag => solver.gen_zig_zag_path()?,
+            CycleKind::SpanningTree { seed } => solver.gen_spanning_tree_path(seed)?,
         }
+        solver.index_cycle();
+        Ok(solver)
     }
 
     fn new_node(&mut self, x: usize, y: usize) {
         let pos = Position {
-            x: (x * NODE_SIZE) as isize,
-            y: (y * NODE_SIZE) as isize,
+            x: x as isize,
+            y: y as isize,
         };
         self.board[x][y] = pos;
-        self.path.push(pos)
+        self.cycle_index[self.config.index(pos)] = Some(self.path.len());
+        self.path.push(pos);
     }
 
-    fn inc_cursor(&mut self) {
-        let end = self.path.len() - 1;
-        if self.cursor == end {
-            self.cursor = 0;
-        } else {
-            self.cursor += 1;
+    fn clear_path(&mut self) {
+        self.path.clear();
+        self.cycle_index.fill(None);
+    }
+
+    fn index_cycle(&mut self) {
+        self.checked = None;
+        self.cycle_index.fill(None);
+        for (i, pos) in self.path.iter().enumerate() {
+            self.cycle_index[self.config.index(*pos)] = Some(i);
+        }
+        let config = self.config;
+        let cycle_index = &self.cycle_index;
+        self.cycle_neighbours.clear();
+        self.cycle_neighbours.extend(self.path.iter().map(|pos| {
+            Direction::ALL.map(|direction| {
+                let neighbour = config.wrap(pos.step(direction));
+                if config.contains(neighbour) {
+                    cycle_index[config.index(neighbour)]
+                } else {
+                    None
+                }
+            })
+        }));
+    }
+
+    /// Follows the Hamiltonian cycle but skips ahead towards the apple when
+    /// that is safe. A shortcut must neither pass the apple nor come closer
+    /// than `SHORTCUT_BUFFER` cells, plus the growth still pending, to the
+    /// first body node ahead of the head in cycle order. The skipped cells
+    /// are empty, so the body always stays behind the head on the cycle and
+    /// following it can never run into the snake. Once the snake covers half
+    /// of the board the shortcuts are not worth the risk anymore and the cycle
+    /// is followed strictly. All of this only holds once the snake is on
+    /// track, until then it is steered onto the cycle, see `steer_onto_cycle`.
+    pub fn make_move(&mut self, view: &GameView) -> Option<Direction> {
+        if !self.is_on_track(view) {
+            self.track = None;
+            return self.steer_onto_cycle(view);
         }
+        let len = self.path.len();
+        let head = view.head();
+        let dist = |to: Position| self.cycle_dist(head, to);
+
+        let next = self.path[(self.get_ham_path_index(head)? + 1) % len];
+        if view.snake.tail.len() * 2 >= len {
+            let direction = self.config.direction_to(head, next)?;
+            self.track = Some((next, view.apple));
+            return Some(direction);
+        }
+
+        let apple_dist = dist(view.apple).unwrap_or(1);
+        let body_dist = view
+            .snake
+            .tail
+            .iter()
+            .skip(1)
+            .filter_map(|node| dist(*node))
+            .filter(|dist| *dist > 0)
+            .min()
+            .unwrap_or(len);
+        let buffer = SHORTCUT_BUFFER + 1 + view.snake.growth();
+        let max_skip = apple_dist.min(body_dist.saturating_sub(buffer));
+
+        let shortcut = Direction::ALL
+            .iter()
+            .map(|direction| (*direction, self.config.wrap(head.step(*direction))))
+            .filter(|(_, pos)| !view.is_blocked(*pos))
+            .filter_map(|(direction, pos)| Some((direction, dist(pos)?)))
+            .filter(|(_, skip)| *skip > 1 && *skip <= max_skip)
+            .filter(|(direction, _)| self.slack_after(view, *direction).is_some())
+            .max_by_key(|(_, skip)| *skip);
+        let direction = match shortcut {
+            Some((direction, _)) => direction,
+            None => self.config.direction_to(head, next)?,
+        };
+        self.track = Some((self.config.wrap(head.step(direction)), view.apple));
+        Some(direction)
     }
 
-    fn next_ham(&mut self) -> Option<Position> {
-        let cursor = self.inc_cursor();
-        self.path.get(self.cursor - 1).copied()
+    /// Whether the body of `snake` lies on the cycle in the order the snake
+    /// moves along it. There may be gaps between two nodes, but the whole
+    /// body has to fit into a single lap behind the head.
+    pub fn is_in_cycle_order(&self, snake: &Snake) -> bool {
+        let mut span = 0;
+        for (node, behind) in snake.tail.iter().zip(snake.tail.iter().skip(1)) {
+            match self.cycle_dist(*behind, *node) {
+                Some(dist) => span += dist,
+                None => return false,
+            }
+        }
+        span < self.path.len()
     }
 
-    pub fn make_move(&mut self, tail: &Tail, apple: Position) -> Option<Direction> {
-        let head = tail.front().unwrap();
-        let ham_head = self.next_ham()?;
+    /// Whether the body lies on the cycle in cycle order and strictly
+    /// following the cycle never runs into it, which `make_move` relies on.
+    pub fn is_on_track(&self, view: &GameView) -> bool {
+        self.track == Some((view.head(), view.apple))
+            || self.is_in_cycle_order(view.snake)
+                && self.cycle_slack(view.snake, Some(view.apple)).is_some()
+    }
 
-        // let search_head = self
-        //	.searcher
-        //	.a_star(*head, apple, tail)
-        //	.expect("failed to find path")
-        //	.last()
-        //	.copied()?;
+    /// Whether the snake is on track or can be steered onto the cycle with
+    /// its next move.
+    pub fn can_follow_cycle(&self, view: &GameView) -> bool {
+        self.is_on_track(view) || self.steer_onto_cycle(view).is_some()
+    }
 
-        let ham_index = self
-            .get_ham_path_index(ham_head)
-            .expect("failed to find ham_index");
-        let head_index = self
-            .get_ham_path_index(*head)
-            .expect("failed to find head_index");
-        println!(
-            "head: {} :: ham: {} :: cursor: {}",
-            head_index, ham_index, self.cursor
-        );
-        return Position::to_direction(*head, ham_head);
-        // dbg!((search_head, ham_head));
-        // if search_index > head_index {
-        //	println!("use search head");
-        // } else {
-        //	println!("use ham head");
-        //	Position::to_direction(*head, ham_head)
-        // }
-    }
-
-    pub fn match_starting_pos(&self, tail: &Tail) -> Option<usize> {
-        let head = tail.front()?;
-        self.get_ham_path_index(*head)
-    }
-
-    pub fn init(&mut self, tail: &Tail) {
-        let starting_pos = self
-            .match_starting_pos(tail)
-            .expect("failed to match starting pos to head of snake");
-        self.cursor = starting_pos - 1;
-    }
-
-    pub fn gen_zig_zag_path(&mut self) {
-        for x in 0..NODE_LEN_X {
-            match x % 2 {
-                0 => {
-                    for y in (0..NODE_LEN_Y - 1).rev() {
-                        self.new_node(x, y);
-                    }
+    /// Reverses the cycle if the snake can only be steered onto it safely
+    /// when it is walked the other way round.
+    pub fn orient_cycle(&mut self, view: &GameView) {
+        if self.can_follow_cycle(view) {
+            return;
+        }
+        self.path.reverse();
+        self.index_cycle();
+        if !self.can_follow_cycle(view) {
+            self.path.reverse();
+            self.index_cycle();
+        }
+    }
+
+    /// A move after which strictly following the cycle never runs into the
+    /// body, the one leaving the most room to spare and preferably the one
+    /// onto the next cell of the cycle. Once the snake followed the cycle for
+    /// as many moves as it is long, its body is in cycle order. `None` if
+    /// every move is fatal sooner or later.
+    fn steer_onto_cycle(&self, view: &GameView) -> Option<Direction> {
+        let head = view.head();
+        let next = self.path[(self.get_ham_path_index(head)? + 1) % self.path.len()];
+        Direction::ALL
+            .iter()
+            .filter_map(|direction| Some((*direction, self.slack_after(view, *direction)?)))
+            .max_by_key(|(direction, slack)| {
+                (*slack, self.config.wrap(head.step(*direction)) == next)
+            })
+            .map(|(direction, _)| direction)
+    }
+
+    /// `cycle_slack` once the snake moved in `direction`, `None` if it doesn't
+    /// survive the move.
+    fn slack_after(&self, view: &GameView, direction: Direction) -> Option<usize> {
+        let head = view.head();
+        let pos = self.config.wrap(head.step(direction));
+        if !view.snake.direction_is_legal(direction) || self.get_ham_path_index(pos).is_none() {
+            return None;
+        }
+        let mut snake = view.snake.walk(&[head, pos], &self.config);
+        if snake.ate_itself() {
+            return None;
+        }
+        if pos == view.apple {
+            snake.grow();
+            return self.cycle_slack(&snake, None);
+        }
+        self.cycle_slack(&snake, Some(view.apple))
+    }
+
+    /// By how many moves strictly following the cycle from the head of
+    /// `snake` misses its body at the closest, `None` if it runs into it, see
+    /// `Snake::vacated_after`. Eating `apple` on the way keeps the body
+    /// around for one more move, apples that only show up later are not
+    /// foreseen.
+    fn cycle_slack(&self, snake: &Snake, apple: Option<Position>) -> Option<usize> {
+        let head = snake.tail[0];
+        let eaten_after = apple
+            .and_then(|apple| self.cycle_dist(head, apple))
+            .filter(|dist| *dist > 0);
+        let mut slack = self.path.len();
+        for (pos, moves) in snake.vacated_after().skip(1) {
+            let moves = moves + usize::from(matches!(eaten_after, Some(at) if at < moves));
+            match self.cycle_dist(head, pos)? {
+                0 => {}
+                dist => slack = slack.min(dist.checked_sub(moves)?),
+            }
+        }
+        Some(slack)
+    }
+
+    /// Reshapes the cycle so the apple comes up sooner after the head, see
+    /// `Splice`. Only the stretch between the head and the first body
+    /// node ahead of it is rearranged, so the body keeps its order on the
+    /// cycle and following the cycle stays safe.
+    pub fn repair_cycle(&mut self, view: &GameView) {
+        let Some(mut head) = self.get_ham_path_index(view.head()) else {
+            return;
+        };
+        let len = self.path.len();
+        let body = self.body_ahead(view);
+        // Moving along the cycle brings the apple and what a splice makes of
+        // it closer by the same number of cells. Unless the apple or the
+        // cycle changed, only splices using cells freed up since the last
+        // check can help.
+        let mut fresh = match self.checked {
+            Some((old_head, old_body, apple)) if apple == view.apple => {
+                let moved = (head + len - old_head) % len;
+                let free = (old_body + len - old_head) % len;
+                free.saturating_sub(moved).min(body)
+            }
+            _ => 0,
+        };
+        while let Some(splice) = self.best_splice(view, head, body, fresh) {
+            self.path.rotate_left(head);
+            self.path = splice.apply(&self.path);
+            self.index_cycle();
+            self.track = None;
+            head = 0;
+            fresh = 0;
+        }
+        self.checked = Some((head, (head + body) % len, view.apple));
+    }
+
+    /// Number of moves along the cycle from the head to the first body node
+    /// ahead of it, at most one lap.
+    fn body_ahead(&self, view: &GameView) -> usize {
+        let len = self.path.len();
+        view.snake
+            .tail
+            .iter()
+            .skip(1)
+            .filter_map(|node| self.cycle_dist(view.head(), *node))
+            .filter(|dist| *dist > 0)
+            .min()
+            .unwrap_or(len)
+            .min(len - 1)
+    }
+
+    /// The splice that brings the apple closest to the head, which is at
+    /// `head` in `path`, or `None` if no splice helps. The indices of the
+    /// splice, `body` for the first body node ahead and `fresh` for the
+    /// first cell not checked yet count from the head on.
+    fn best_splice(
+        &self,
+        view: &GameView,
+        head: usize,
+        body: usize,
+        fresh: usize,
+    ) -> Option<Splice> {
+        let len = self.path.len();
+        // Cells off the cycle are as good as behind the first body node.
+        let from_head = move |index: Option<usize>| match index {
+            Some(i) if i >= head => i - head,
+            Some(i) => i + len - head,
+            None => usize::MAX,
+        };
+        let neighbours = |i: usize| {
+            let i = if i < len - head {
+                i + head
+            } else {
+                i + head - len
+            };
+            self.cycle_neighbours[i].map(from_head)
+        };
+        let adjacent = |a: usize, b: usize| neighbours(a).contains(&b);
+
+        let apple = from_head(self.get_ham_path_index(view.apple));
+        if apple >= body {
+            return None;
+        }
+
+        let mut best = None;
+        let mut best_dist = apple;
+        for start in 1..=apple {
+            for after in neighbours(start - 1) {
+                if after <= start + 1 || after > body || !adjacent(start, after - 1) {
+                    continue;
+                }
+                let end = after - 1;
+                let loop_len = end - start + 1;
+                if apple > end && apple - loop_len >= best_dist {
+                    continue;
                 }
-                _ => {
-                    for y in 0..NODE_LEN_Y - 1 {
-                        self.new_node(x, y);
+                let outer = |i: usize| if i < start { i } else { i - loop_len };
+                // If the apple moves into the loop, splicing it in only gets
+                // the apple closer right behind the head, a stretch often far
+                // shorter than the loop. Otherwise it has to go behind the
+                // apple. A loop checked before has to go behind a fresh cell.
+                let ahead = best_dist.saturating_sub(1);
+                let first_new = if after <= fresh { fresh } else { 0 };
+                let (front, back) = if apple <= end {
+                    let back = (end + 1).max(first_new)..(ahead + loop_len).min(body);
+                    (first_new..ahead.min(start), back)
+                } else {
+                    (0..0, apple.max(first_new)..body)
+                };
+                let mut splice_at = |before: usize, entry: usize| {
+                    if (start..=end).contains(&before) || outer(before) >= body - loop_len {
+                        return;
+                    }
+                    let behind = if before + 1 == start {
+                        after
+                    } else {
+                        before + 1
+                    };
+                    for forward in [true, false] {
+                        let step = if forward { loop_len - 1 } else { 1 };
+                        let exit = start + (entry - start + step) % loop_len;
+                        if !adjacent(exit, behind) {
+                            continue;
+                        }
+                        let dist = if apple <= end {
+                            let offset = if forward {
+                                apple + loop_len - entry
+                            } else {
+                                entry + loop_len - apple
+                            };
+                            outer(before) + 1 + offset % loop_len
+                        } else if outer(apple) <= outer(before) {
+                            outer(apple)
+                        } else {
+                            apple
+                        };
+                        if dist < best_dist {
+                            best_dist = dist;
+                            best = Some(Splice {
+                                start,
+                                end,
+                                before,
+                                entry,
+                                forward,
+                            });
+                        }
+                    }
+                };
+                if apple <= end || first_new > 0 {
+                    for before in front.chain(back) {
+                        for entry in neighbours(before) {
+                            if (start..=end).contains(&entry) {
+                                splice_at(before, entry);
+                            }
+                        }
                     }
+                } else {
+                    for entry in start..=end {
+                        for before in neighbours(entry) {
+                            splice_at(before, entry);
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Generates a zig-zag cycle. It snakes along the columns if the board
+    /// has an even width and along the rows otherwise, so one side has to be
+    /// even. Boards with wrapping edges are treated like any other.
+    pub fn gen_zig_zag_path(&mut self) -> Result<()> {
+        let (width, height) = (self.config.width, self.config.height);
+        if width < 2 || height < 2 {
+            bail!(
+                "there is no Hamiltonian cycle on a {}x{} board",
+                width,
+                height
+            );
+        }
+        if width % 2 == 1 && height % 2 == 1 {
+            bail!(
+                "zig-zag cycles need a board with an even side, got {}x{}",
+                width,
+                height
+            );
+        }
+
+        self.clear_path();
+        let transposed = width % 2 == 1;
+        let (cols, rows) = if transposed {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let mut node = |col: usize, row: usize| {
+            if transposed {
+                self.new_node(row, col);
+            } else {
+                self.new_node(col, row);
+            }
+        };
+        for col in 0..cols {
+            if col % 2 == 0 {
+                for row in (0..rows - 1).rev() {
+                    node(col, row);
+                }
+            } else {
+                for row in 0..rows - 1 {
+                    node(col, row);
                 }
             }
         }
-        for x in (0..NODE_LEN_X).rev() {
-            self.new_node(x, NODE_LEN_Y - 1);
+        for col in (0..cols).rev() {
+            node(col, rows - 1);
+        }
+        Ok(())
+    }
+
+    /// Generates a random cycle. A random spanning tree is built on the grid of
+    /// 2x2 blocks, every block starts out as a tiny cycle of its own and the
+    /// cycles of two blocks are merged wherever the tree connects them. Only
+    /// works if both sides of the board are even.
+    pub fn gen_spanning_tree_path(&mut self, seed: u64) -> Result<()> {
+        let (width, height) = (self.config.width, self.config.height);
+        if width % 2 == 1 || height % 2 == 1 {
+            bail!(
+                "random Hamiltonian cycles need an even width and height, got {}x{}",
+                width,
+                height
+            );
+        }
+
+        let config = self.config;
+        let mut links = vec![[false; Direction::ALL.len()]; config.cells()];
+        let link = |links: &mut Vec<[bool; 4]>, x: usize, y: usize, dir: Direction, on: bool| {
+            let pos = Position {
+                x: x as isize,
+                y: y as isize,
+            };
+            links[config.index(pos)][dir as usize] = on;
+            links[config.index(pos.step(dir))][dir.opposite() as usize] = on;
+        };
+
+        for (bx, by) in (0..width / 2).flat_map(|bx| (0..height / 2).map(move |by| (bx, by))) {
+            let (x, y) = (bx * 2, by * 2);
+            link(&mut links, x, y, Direction::Right, true);
+            link(&mut links, x, y, Direction::Down, true);
+            link(&mut links, x + 1, y, Direction::Down, true);
+            link(&mut links, x, y + 1, Direction::Right, true);
+        }
+
+        for (x, y, dir) in random_spanning_tree(width / 2, height / 2, seed) {
+            let (x, y) = (x * 2, y * 2);
+            if dir == Direction::Right {
+                link(&mut links, x + 1, y, Direction::Down, false);
+                link(&mut links, x + 2, y, Direction::Down, false);
+                link(&mut links, x + 1, y, Direction::Right, true);
+                link(&mut links, x + 1, y + 1, Direction::Right, true);
+            } else {
+                link(&mut links, x, y + 1, Direction::Right, false);
+                link(&mut links, x, y + 2, Direction::Right, false);
+                link(&mut links, x, y + 1, Direction::Down, true);
+                link(&mut links, x + 1, y + 1, Direction::Down, true);
+            }
+        }
+
+        self.clear_path();
+        let (mut pos, mut came_from) = (Position::default(), None);
+        loop {
+            self.new_node(pos.x as usize, pos.y as usize);
+            let next = Direction::ALL
+                .iter()
+                .copied()
+                .find(|dir| links[config.index(pos)][*dir as usize] && Some(*dir) != came_from)
+                .expect("every cell of the merged cycles has two links");
+            pos = pos.step(next);
+            came_from = Some(next.opposite());
+            if pos == Position::default() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Checks that `path` visits every cell of the board exactly once, never
+    /// enters a wall of `level`, only ever moves to a neighbouring cell and
+    /// leads from its last cell back to the first one.
+    pub fn validate_path(&self, level: &Level) -> Result<()> {
+        if self.path.len() != self.config.cells() {
+            bail!(
+                "the cycle has {} cells but the board {}",
+                self.path.len(),
+                self.config.cells()
+            );
+        }
+        let mut visited = vec![false; self.config.cells()];
+        for (i, pos) in self.path.iter().enumerate() {
+            if !self.config.contains(*pos) {
+                bail!("cycle cell {} at {:?} is off the board", i, pos);
+            }
+            if level.is_wall(*pos) {
+                bail!("cycle cell {} at {:?} is a wall", i, pos);
+            }
+            let index = self.config.index(*pos);
+            if visited[index] {
+                bail!("the cycle visits {:?} twice", pos);
+            }
+            visited[index] = true;
+
+            let next = self.path[(i + 1) % self.path.len()];
+            if self.config.direction_to(*pos, next).is_none() {
+                bail!("cycle cells {:?} and {:?} are not adjacent", pos, next);
+            }
         }
+        Ok(())
     }
 
     pub fn get_ham_path_index(&self, pos: Position) -> Option<usize> {
-        self.path.iter().position(|p| *p == pos)
+        if !self.config.contains(pos) {
+            return None;
+        }
+        self.cycle_index[self.config.index(pos)]
+    }
+
+    /// Number of moves it takes to get from `from` to `to` when following the
+    /// cycle, `None` if either of them is not on it.
+    pub fn cycle_dist(&self, from: Position, to: Position) -> Option<usize> {
+        let len = self.path.len();
+        Some((self.get_ham_path_index(to)? + len - self.get_ham_path_index(from)?) % len)
     }
 }
 
-const ROTATION_MATRIX: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+/// Rearrangement of a Hamiltonian cycle. Where the cycle runs along two
+/// opposite sides of a grid square, swapping them for the other two sides cuts
+/// `start..=end` out as a separate loop. The loop is spliced back in behind
+/// `before` at another such square, entering it at `entry` and walking it in
+/// its old direction if `forward`, which gives a Hamiltonian cycle again.
+#[derive(Debug)]
+struct Splice {
+    start: usize,
+    end: usize,
+    before: usize,
+    entry: usize,
+    forward: bool,
+}
 
-#[derive(Debug, Clone, Copy)]
-pub struct Node {
-    pos: Position,
-    parent_id: Option<usize>,
-    id: usize,
-    g_cost: isize,
-    h_cost: isize,
-    f_cost: isize,
+impl Splice {
+    fn apply(&self, path: &[Position]) -> Vec<Position> {
+        let inner = &path[self.start..=self.end];
+        let entry = self.entry - self.start;
+        let spliced = if self.forward {
+            inner[entry..]
+                .iter()
+                .chain(&inner[..entry])
+                .copied()
+                .collect::<Vec<_>>()
+        } else {
+            inner[..=entry]
+                .iter()
+                .rev()
+                .chain(inner[entry + 1..].iter().rev())
+                .copied()
+                .collect()
+        };
+
+        let mut result = Vec::with_capacity(path.len());
+        for pos in path[..self.start].iter().chain(&path[self.end + 1..]) {
+            result.push(*pos);
+            if *pos == path[self.before] {
+                result.extend_from_slice(&spliced);
+            }
+        }
+        result
+    }
 }
 
-impl Hash for Node {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.pos.hash(state)
+/// Edges of a random spanning tree on a `width` x `height` grid, found with
+/// Kruskal's algorithm on randomly ordered edges. Every edge is given as a
+/// cell and the direction, `Right` or `Down`, of its neighbour.
+fn random_spanning_tree(width: usize, height: usize, seed: u64) -> Vec<(usize, usize, Direction)> {
+    let mut edges = Vec::with_capacity(width * height * 2);
+    for x in 0..width {
+        for y in 0..height {
+            if x + 1 < width {
+                edges.push((x, y, Direction::Right));
+            }
+            if y + 1 < height {
+                edges.push((x, y, Direction::Down));
+            }
+        }
     }
+    edges.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let mut parent = (0..width * height).collect::<Vec<_>>();
+    let find = |parent: &mut Vec<usize>, mut i: usize| {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    };
+    edges
+        .into_iter()
+        .filter(|&(x, y, dir)| {
+            let (nx, ny) = if dir == Direction::Right {
+                (x + 1, y)
+            } else {
+                (x, y + 1)
+            };
+            let a = find(&mut parent, y * width + x);
+            let b = find(&mut parent, ny * width + nx);
+            parent[a] = b;
+            a != b
+        })
+        .collect()
 }
 
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.f_cost > other.f_cost {
-            Ordering::Greater
-        } else if self.f_cost < other.f_cost {
-            Ordering::Less
-        } else {
-            Ordering::Equal
+/// Result of a search, the cells from the start up to and including the goal
+/// together with the cost of walking them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub cells: Vec<Position>,
+    pub cost: isize,
+}
+
+impl Path {
+    /// The cell to move to first, `None` if the search started on the goal.
+    pub fn first_step(&self) -> Option<Position> {
+        self.cells.get(1).copied()
+    }
+}
+
+/// How the open set of `Searcher` orders its nodes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Priority {
+    /// Path cost plus heuristic, A*.
+    AStar,
+    /// Path cost alone, Dijkstra's algorithm.
+    Cost,
+    /// Heuristic alone, greedy best-first search. Fast, but the path it finds
+    /// is not always the shortest.
+    Heuristic,
+}
+
+impl Priority {
+    const fn f_cost(self, g_cost: isize, h_cost: isize) -> isize {
+        match self {
+            Self::AStar => g_cost + h_cost,
+            Self::Cost => g_cost,
+            Self::Heuristic => h_cost,
         }
     }
 }
 
-impl Default for Node {
-    fn default() -> Self {
-        Self {
-            pos: Position::default(),
-            parent_id: None,
-            id: 0,
-            g_cost: 0,
-            h_cost: 0,
-            f_cost: 0,
+/// Work done by one or more searches, see `Searcher::stats`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+    pub searches: usize,
+    /// Nodes taken off the open set and expanded.
+    pub expanded: usize,
+    /// Nodes put on the open set.
+    pub generated: usize,
+    /// Largest number of nodes on the open set at once.
+    pub peak_open: usize,
+    /// Largest number of nodes on the closed set at once.
+    pub peak_closed: usize,
+    pub duration: Duration,
+}
+
+impl SearchStats {
+    /// Raises the peaks to the current size of the open and closed set.
+    pub fn track(&mut self, open: usize, closed: usize) {
+        self.peak_open = self.peak_open.max(open);
+        self.peak_closed = self.peak_closed.max(closed);
+    }
+
+    /// Adds the work of `other`, the peaks are the larger of the two.
+    pub fn add(&mut self, other: &Self) {
+        self.searches += other.searches;
+        self.expanded += other.expanded;
+        self.generated += other.generated;
+        self.track(other.peak_open, other.peak_closed);
+        self.duration += other.duration;
+    }
+
+    /// Average number of nodes expanded by a search.
+    pub fn expanded_per_search(&self) -> f32 {
+        self.expanded as f32 / self.searches.max(1) as f32
+    }
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} searches, {} expanded, {} generated, peak open {}, peak closed {}, {:.2?}",
+            self.searches,
+            self.expanded,
+            self.generated,
+            self.peak_open,
+            self.peak_closed,
+            self.duration
+        )
+    }
+}
+
+/// One expansion of a traced search, see `Searcher::trace_a_star`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// The node taken off the open set and closed.
+    pub current: Position,
+    /// The cells put on the open set while expanding `current`.
+    pub opened: Vec<Position>,
+}
+
+/// Every expansion of a single search, in order, and the path it found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchTrace {
+    pub start: Position,
+    pub steps: Vec<TraceStep>,
+    pub path: Option<Path>,
+}
+
+impl SearchTrace {
+    /// The cells on the open and on the closed set after the first `steps`
+    /// expansions.
+    pub fn sets_after(&self, steps: usize) -> (HashSet<Position>, HashSet<Position>) {
+        let mut open = HashSet::new();
+        let mut closed = HashSet::new();
+        open.insert(self.start);
+        for step in self.steps.iter().take(steps) {
+            open.remove(&step.current);
+            closed.insert(step.current);
+            open.extend(step.opened.iter().copied());
         }
+        (open, closed)
+    }
+}
+
+/// Connected areas of free cells, see `Searcher::regions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regions {
+    /// Region of every cell by `BoardConfig::index`, `None` for obstacles.
+    pub labels: Vec<Option<usize>>,
+    /// Number of cells in every region.
+    pub sizes: Vec<usize>,
+}
+
+impl Regions {
+    /// Size of the region `pos` belongs to, 0 for obstacles.
+    pub fn size_at(&self, config: &BoardConfig, pos: Position) -> usize {
+        self.labels[config.index(pos)].map_or(0, |label| self.sizes[label])
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Node {
+    pos: Position,
+    g_cost: isize,
+    h_cost: isize,
+    f_cost: isize,
+    /// Number of steps from the start, which only matches `g_cost` while
+    /// every step costs 1.
+    moves: isize,
+}
+
+/// The open set is a max-heap, so the node with the lowest f-cost compares
+/// greatest. Ties go to the lower h-cost, the node closer to the goal, and
+/// then to the position and the cheaper node of it so the order never
+/// depends on the heap.
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_cost
+            .cmp(&self.f_cost)
+            .then_with(|| other.h_cost.cmp(&self.h_cost))
+            .then_with(|| (other.pos.y, other.pos.x).cmp(&(self.pos.y, self.pos.x)))
+            .then_with(|| other.g_cost.cmp(&self.g_cost))
+            .then_with(|| other.moves.cmp(&self.moves))
     }
 }
 
@@ -172,49 +847,170 @@ impl PartialOrd for Node {
     }
 }
 
-impl PartialEq for Node {
-    fn eq(&self, other: &Self) -> bool {
-        self.pos == other.pos
-    }
+/// What the searcher knows about one cell of the board. A field only holds
+/// for the current search if its stamp matches the generation of the
+/// searcher, so a search starts over without touching the cells.
+#[derive(Debug, Default, Clone, Copy)]
+struct Cell {
+    /// Generation of the search that opened the cell.
+    opened: u32,
+    /// Generation of the search that closed the cell.
+    closed: u32,
+    /// Generation of the obstacle cache that blocks the cell.
+    blocked: u32,
+    /// Number of moves before the search may enter a blocked cell,
+    /// `isize::MAX` if it stays blocked for good.
+    free_at: isize,
+    /// Lowest cost found so far from the start of the search.
+    g_cost: isize,
+    /// Index of the cell the cheapest path found so far comes from, the
+    /// start is its own parent.
+    parent: usize,
+    /// Generation of the flood fill that reached the cell.
+    filled: u32,
+    /// Region the flood fill put the cell in.
+    region: usize,
 }
 
-impl Eq for Node {}
-
 #[derive(Debug)]
 pub struct Searcher {
-    node_link: Vec<Node>,
-    cursor: usize,
-    open: BTreeSet<Node>,
-    closed: HashSet<Position>,
-    obstacles: HashSet<Position>,
-    childs: [Node; ROTATION_MATRIX.len()],
+    config: BoardConfig,
+    /// State of every cell by `BoardConfig::index`, allocated once so that
+    /// searches on the same board don't allocate.
+    cells: Vec<Cell>,
+    /// Stamp of the current search, see `Cell`.
+    generation: u32,
+    /// Stamp of the current obstacle cache, see `Cell`.
+    obstacle_generation: u32,
+    /// Nodes waiting to be expanded. A position whose cost improves is pushed
+    /// again instead of updated in place, the outdated node is skipped once
+    /// it comes up.
+    open: BinaryHeap<Node>,
+    /// Number of cells closed by the current search.
+    closed: usize,
+    /// Whether every cell is a wall, by `BoardConfig::index`.
+    walls: Vec<bool>,
+    priority: Priority,
+    neighbourhood: Neighbourhood,
+    /// What stepping onto every cell costs by `BoardConfig::index`, at least 1.
+    terrain: Vec<isize>,
+    /// Work done by the last search.
+    stats: SearchStats,
+    /// Work done by every search so far.
+    totals: SearchStats,
+    /// Expansions of the current search, only recorded by `trace_a_star`.
+    trace: Option<Vec<TraceStep>>,
+    /// Stamp of the current flood fill, see `Cell`.
+    fill_generation: u32,
+    /// Cells still to visit by `flood_fill`, kept so fills don't allocate.
+    fill_stack: Vec<Position>,
+    /// Result of the last call of `regions`.
+    regions: Regions,
 }
 
 impl Searcher {
-    pub fn new() -> Self {
+    pub fn new(config: BoardConfig) -> Self {
         Self {
-            node_link: Vec::with_capacity(MAX_NODE_LINK_LEN),
-            cursor: 0,
-            open: BTreeSet::new(),
-            obstacles: HashSet::with_capacity(MAX_NODE_LINK_LEN),
-            closed: HashSet::with_capacity(MAX_NODE_LINK_LEN),
-            childs: [Node::default(); ROTATION_MATRIX.len()],
+            config,
+            cells: vec![Cell::default(); config.cells()],
+            generation: 1,
+            obstacle_generation: 1,
+            open: BinaryHeap::with_capacity(config.cells()),
+            closed: 0,
+            walls: vec![false; config.cells()],
+            priority: Priority::AStar,
+            neighbourhood: Neighbourhood::Four,
+            terrain: vec![1; config.cells()],
+            stats: SearchStats::default(),
+            totals: SearchStats::default(),
+            trace: None,
+            fill_generation: 1,
+            fill_stack: Vec::with_capacity(config.cells()),
+            regions: Regions {
+                labels: Vec::with_capacity(config.cells()),
+                sizes: Vec::new(),
+            },
+        }
+    }
+
+    /// Static obstacles of the level which are added to the obstacles of
+    /// every search.
+    pub fn set_walls(&mut self, walls: &[Position]) {
+        let config = self.config;
+        self.walls.fill(false);
+        for wall in walls.iter().filter(|wall| config.contains(**wall)) {
+            self.walls[config.index(*wall)] = true;
         }
     }
 
+    /// What stepping onto every cell costs, see `Level::costs`. Every cell
+    /// costs 1 unless set otherwise.
+    pub fn set_terrain(&mut self, costs: &[isize]) {
+        self.terrain.clear();
+        self.terrain.extend_from_slice(costs);
+    }
+
+    /// The cells a search may step to from any given cell, `Four` unless set
+    /// otherwise.
+    pub const fn set_neighbourhood(&mut self, neighbourhood: Neighbourhood) {
+        self.neighbourhood = neighbourhood;
+    }
+
     pub fn update_obs_cache(&mut self, obstacles: &VecDeque<Position>) {
-        self.obstacles.clear();
+        self.clear_obstacles();
         for obstacle in obstacles {
-            self.obstacles.insert(*obstacle);
+            self.block(*obstacle, isize::MAX);
+        }
+    }
+
+    /// Blocks the walls for good and every node of `snake` until it has
+    /// moved on, see `Snake::vacated_after`.
+    pub fn update_timed_obs_cache(&mut self, snake: &Snake) {
+        self.clear_obstacles();
+        for (pos, moves) in snake.vacated_after() {
+            self.block(pos, moves as isize);
+        }
+    }
+
+    /// Blocks `pos` for at least `free_at` moves, cells off the board are
+    /// never entered anyway.
+    fn block(&mut self, pos: Position, free_at: isize) {
+        if !self.config.contains(pos) {
+            return;
+        }
+        let generation = self.obstacle_generation;
+        let cell = &mut self.cells[self.config.index(pos)];
+        if cell.blocked == generation {
+            cell.free_at = cell.free_at.max(free_at);
+        } else {
+            cell.blocked = generation;
+            cell.free_at = free_at;
         }
     }
 
+    fn clear_obstacles(&mut self) {
+        if self.obstacle_generation == u32::MAX {
+            self.cells.iter_mut().for_each(|cell| cell.blocked = 0);
+            self.obstacle_generation = 0;
+        }
+        self.obstacle_generation += 1;
+    }
+
+    /// Forgets the last search and the obstacles in constant time by moving
+    /// on to the next generation. Only once the stamps run out every cell is
+    /// cleared.
     pub fn reset(&mut self) {
+        if self.generation == u32::MAX {
+            self.cells.iter_mut().for_each(|cell| {
+                cell.opened = 0;
+                cell.closed = 0;
+            });
+            self.generation = 0;
+        }
+        self.generation += 1;
+        self.clear_obstacles();
         self.open.clear();
-        self.closed.clear();
-        self.node_link.clear();
-        self.obstacles.clear();
-        self.cursor = 0;
+        self.closed = 0;
     }
 
     pub fn a_star(
@@ -222,94 +1018,986 @@ impl Searcher {
         start: Position,
         goal: Position,
         obstacles: &VecDeque<Position>,
-    ) -> Option<Vec<Position>> {
-        self.reset();
-        self.update_obs_cache(obstacles);
+    ) -> Option<Path> {
+        self.search_by(start, goal, obstacles, Priority::AStar)
+    }
 
-        let start_node = Node {
-            pos: start,
-            parent_id: None,
-            id: self.gen_id(),
-            g_cost: 0,
-            h_cost: 0,
-            f_cost: 0,
+    /// Best-first search from `start` to `goal` around `obstacles` that
+    /// expands the nodes in the order given by `priority`.
+    pub fn search_by(
+        &mut self,
+        start: Position,
+        goal: Position,
+        obstacles: &VecDeque<Position>,
+        priority: Priority,
+    ) -> Option<Path> {
+        self.measure(|searcher| {
+            searcher.reset();
+            searcher.update_obs_cache(obstacles);
+            searcher.priority = priority;
+            searcher.search(start, goal)
+        })
+    }
+
+    /// Like `a_star` with the body of `snake` as obstacles, but a body cell
+    /// only blocks the path if the snake still covers it by the time the path
+    /// gets there. Every cell is closed the first time it is reached, so a
+    /// path that would have to arrive at a cell later to find it free can be
+    /// missed, but no path ever runs into the snake.
+    pub fn a_star_timed(&mut self, start: Position, goal: Position, snake: &Snake) -> Option<Path> {
+        self.measure(|searcher| {
+            searcher.reset();
+            searcher.update_timed_obs_cache(snake);
+            searcher.priority = Priority::AStar;
+            searcher.search(start, goal)
+        })
+    }
+
+    /// Like `a_star_timed`, but never steps on `avoid`, e.g. an apple that
+    /// would make the snake grow on the way.
+    pub fn a_star_timed_around(
+        &mut self,
+        start: Position,
+        goal: Position,
+        snake: &Snake,
+        avoid: Position,
+    ) -> Option<Path> {
+        self.measure(|searcher| {
+            searcher.reset();
+            searcher.update_timed_obs_cache(snake);
+            searcher.block(avoid, isize::MAX);
+            searcher.priority = Priority::AStar;
+            searcher.search(start, goal)
+        })
+    }
+
+    /// Stretches `path` by replacing single steps with detours through the
+    /// two free cells beside them for as long as there are any. The cells
+    /// blocked in the last search stay blocked, and as the longer path gets
+    /// to every cell later, it still clears a moving snake.
+    pub fn lengthen(&self, path: &mut Path) {
+        let mut on_path = vec![false; self.config.cells()];
+        for pos in &path.cells {
+            on_path[self.config.index(*pos)] = true;
+        }
+        let mut i = 0;
+        while i + 1 < path.cells.len() {
+            let (from, to) = (path.cells[i], path.cells[i + 1]);
+            let free = |pos: Position| {
+                self.config.contains(pos)
+                    && !self.is_blocked(pos)
+                    && !on_path[self.config.index(pos)]
+            };
+            let detour = self.config.direction_to(from, to).and_then(|direction| {
+                Direction::ALL
+                    .iter()
+                    .filter(|side| **side != direction && **side != direction.opposite())
+                    .map(|side| {
+                        let wrap = |pos: Position| self.config.wrap(pos.step(*side));
+                        (wrap(from), wrap(to))
+                    })
+                    .find(|(a, b)| free(*a) && free(*b))
+            });
+            match detour {
+                Some((a, b)) => {
+                    path.cells.insert(i + 1, b);
+                    path.cells.insert(i + 1, a);
+                    path.cost += self.terrain(a) + self.terrain(b);
+                    on_path[self.config.index(a)] = true;
+                    on_path[self.config.index(b)] = true;
+                }
+                None => i += 1,
+            }
+        }
+    }
+
+    /// Like `a_star`, but records every expansion so the search can be
+    /// replayed step by step.
+    pub fn trace_a_star(
+        &mut self,
+        start: Position,
+        goal: Position,
+        obstacles: &VecDeque<Position>,
+    ) -> SearchTrace {
+        self.trace = Some(Vec::new());
+        let path = self.a_star(start, goal, obstacles);
+        SearchTrace {
+            start,
+            steps: self.trace.take().unwrap_or_default(),
+            path,
+        }
+    }
+
+    /// Runs `search` as a single search, whose work is recorded in `stats`
+    /// and added to `totals`.
+    pub fn measure<T>(&mut self, search: impl FnOnce(&mut Self) -> T) -> T {
+        self.stats = SearchStats {
+            searches: 1,
+            ..SearchStats::default()
         };
-        self.open.insert(start_node);
-        self.node_link.push(start_node);
+        let started = Instant::now();
+        let result = search(self);
+        self.stats.duration = started.elapsed();
+        self.totals.add(&self.stats);
+        result
+    }
 
-        while let Some(current) = self.open.pop_first() {
-            debug_assert!(self.open.len() < MAX_NODE_LINK_LEN, "Infinite Loop!");
+    /// Work done by the last search.
+    pub const fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Lets searches run by `measure` outside of this module count their work.
+    pub const fn stats_mut(&mut self) -> &mut SearchStats {
+        &mut self.stats
+    }
+
+    /// Work done by every search of this searcher so far.
+    pub const fn totals(&self) -> &SearchStats {
+        &self.totals
+    }
+
+    /// Number of cells reachable from `from`, itself included, without
+    /// crossing a wall or the body of the snake in `view`. The body counts as
+    /// blocked for good, so this is a lower bound on the room the snake has.
+    pub fn reachable_area(&mut self, view: &GameView, from: Position) -> usize {
+        if !self.config.contains(from) {
+            return 0;
+        }
+        self.update_obs_cache(&view.snake.tail);
+        self.next_fill();
+        self.flood_fill(from, 0)
+    }
+
+    /// Splits the cells that are neither walls nor covered by the snake in
+    /// `view` into connected regions.
+    pub fn regions(&mut self, view: &GameView) -> &Regions {
+        self.update_obs_cache(&view.snake.tail);
+        self.next_fill();
+        let mut sizes = std::mem::take(&mut self.regions.sizes);
+        sizes.clear();
+        for index in 0..self.config.cells() {
+            let pos = self.config.position(index);
+            if self.cells[index].filled != self.fill_generation && !self.is_blocked(pos) {
+                sizes.push(self.flood_fill(pos, sizes.len()));
+            }
+        }
+        let generation = self.fill_generation;
+        self.regions.labels.clear();
+        self.regions.labels.extend(
+            self.cells
+                .iter()
+                .map(|cell| (cell.filled == generation).then_some(cell.region)),
+        );
+        self.regions.sizes = sizes;
+        &self.regions
+    }
+
+    /// Puts `from` and every free cell connected to it that the current fill
+    /// hasn't reached yet into region `label` and returns how many cells that
+    /// were.
+    fn flood_fill(&mut self, from: Position, label: usize) -> usize {
+        let generation = self.fill_generation;
+        let mut stack = std::mem::take(&mut self.fill_stack);
+        stack.push(from);
+        let mut size = 0;
+        while let Some(pos) = stack.pop() {
+            let cell = &mut self.cells[self.config.index(pos)];
+            if cell.filled == generation {
+                continue;
+            }
+            cell.filled = generation;
+            cell.region = label;
+            size += 1;
+            let cells = &self.cells;
+            stack.extend(self.neighbours(pos).map(|(next, _)| next).filter(|next| {
+                cells[self.config.index(*next)].filled != generation && !self.is_blocked(*next)
+            }));
+        }
+        self.fill_stack = stack;
+        size
+    }
+
+    /// Forgets the last flood fill in constant time, see `reset`.
+    fn next_fill(&mut self) {
+        if self.fill_generation == u32::MAX {
+            self.cells.iter_mut().for_each(|cell| cell.filled = 0);
+            self.fill_generation = 0;
+        }
+        self.fill_generation += 1;
+    }
+
+    /// The cells a single step away from `pos` in the neighbourhood of the
+    /// search, across the edges if they wrap, next to the cost of the step,
+    /// which is the cost of the neighbourhood times that of the terrain
+    /// entered. Diagonal steps past blocked cells are left out as the corner
+    /// rule says.
+    pub fn neighbours(&self, pos: Position) -> impl Iterator<Item = (Position, isize)> + '_ {
+        let neighbourhood = self.neighbourhood;
+        neighbourhood
+            .offsets(pos)
+            .iter()
+            .filter(move |offset| !self.cuts_corner(pos, **offset))
+            .map(move |&(x, y)| {
+                let next = self.config.wrap(Position {
+                    x: pos.x + x,
+                    y: pos.y + y,
+                });
+                (next, neighbourhood.step_cost((x, y)))
+            })
+            .filter(move |(next, _)| self.config.contains(*next))
+            .map(move |(next, cost)| (next, cost * self.terrain(next)))
+    }
 
-            self.closed.insert(current.pos);
+    /// What stepping onto `pos`, which has to lie on the board, costs on top
+    /// of the step itself.
+    pub fn terrain(&self, pos: Position) -> isize {
+        self.terrain[self.config.index(pos)]
+    }
+
+    /// Lower bound on the cost of any walk from `a` to `b`, which ignores the
+    /// terrain as no cell costs less than 1.
+    pub fn distance(&self, a: Position, b: Position) -> isize {
+        self.neighbourhood.distance(&self.config, a, b)
+    }
+
+    /// Whether the corner rule forbids the diagonal step from `pos` by
+    /// `offset` because of the cells it passes between.
+    fn cuts_corner(&self, pos: Position, (x, y): (isize, isize)) -> bool {
+        let corners = match self.neighbourhood.corners() {
+            Some(corners) if x != 0 && y != 0 => corners,
+            _ => return false,
+        };
+        let blocked = |x, y| {
+            self.is_blocked(self.config.wrap(Position {
+                x: pos.x + x,
+                y: pos.y + y,
+            }))
+        };
+        match corners {
+            Corners::Cut => false,
+            Corners::Touch => blocked(x, 0) && blocked(0, y),
+            Corners::Avoid => blocked(x, 0) || blocked(0, y),
+        }
+    }
+
+    /// Whether `pos` is a wall or in the obstacle cache, no matter for how
+    /// long. Cells off the board are never blocked.
+    pub fn is_blocked(&self, pos: Position) -> bool {
+        self.config.contains(pos) && self.free_at(self.config.index(pos)) > 0
+    }
+
+    /// Number of moves before the cell at `index` may be entered.
+    fn free_at(&self, index: usize) -> isize {
+        let cell = &self.cells[index];
+        if self.walls[index] {
+            isize::MAX
+        } else if cell.blocked == self.obstacle_generation {
+            cell.free_at
+        } else {
+            0
+        }
+    }
+
+    fn search(&mut self, start: Position, goal: Position) -> Option<Path> {
+        let h_cost = self.distance(start, goal);
+        self.open.push(Node {
+            pos: start,
+            g_cost: 0,
+            h_cost,
+            f_cost: self.priority.f_cost(0, h_cost),
+            moves: 0,
+        });
+        let index = self.config.index(start);
+        let generation = self.generation;
+        let cell = &mut self.cells[index];
+        cell.opened = generation;
+        cell.g_cost = 0;
+        cell.parent = index;
+        self.stats.generated += 1;
+
+        while let Some(current) = self.open.pop() {
+            let cell = &mut self.cells[self.config.index(current.pos)];
+            if cell.closed == generation || current.g_cost > cell.g_cost {
+                continue;
+            }
+            cell.closed = generation;
+            self.closed += 1;
+            self.stats.expanded += 1;
+            if let Some(trace) = &mut self.trace {
+                trace.push(TraceStep {
+                    current: current.pos,
+                    opened: Vec::new(),
+                });
+            }
 
             if current.pos == goal {
                 return Some(self.backtrack_path(current));
             }
 
-            self.new_gen_childs(&current);
-            for child in &mut self.childs {
-                if self.obstacles.contains(&child.pos)
-                    || !child.pos.in_range(GAME_LOWER_BOUND, GAME_UPPER_BOUND)
-                    || self.closed.contains(&child.pos)
-                {
-                    continue;
-                }
+            self.new_gen_childs(&current, goal);
+        }
+        None
+    }
 
-                child.g_cost = current.g_cost + 1;
-                child.h_cost = child.pos.mhtn_dist(goal);
-                child.f_cost = child.g_cost + child.h_cost;
+    /// Walks the parent chain from `goal` back to the start of the search.
+    fn backtrack_path(&self, goal: Node) -> Path {
+        let mut index = self.config.index(goal.pos);
+        let mut cells = vec![goal.pos];
+        while self.cells[index].parent != index {
+            index = self.cells[index].parent;
+            cells.push(self.config.position(index));
+        }
+        cells.reverse();
+        Path {
+            cells,
+            cost: goal.g_cost,
+        }
+    }
 
-                match self.open.get(child) {
-                    Some(node) if child.g_cost > node.g_cost => continue,
-                    _ => {
-                        self.open.insert(*child);
-                    }
+    /// Opens the neighbours of `current` that are free by the time they are
+    /// reached, not closed yet and reached cheaper than before.
+    fn new_gen_childs(&mut self, current: &Node, goal: Position) {
+        let mut children = [(Position::default(), 0); 8];
+        let mut count = 0;
+        for child in self.neighbours(current.pos) {
+            children[count] = child;
+            count += 1;
+        }
+        let parent = self.config.index(current.pos);
+        let moves = current.moves + 1;
+        for &(pos, step_cost) in &children[..count] {
+            let index = self.config.index(pos);
+            let g_cost = current.g_cost + step_cost;
+            let cell = self.cells[index];
+            if cell.closed == self.generation || moves < self.free_at(index) {
+                continue;
+            }
+            if cell.opened == self.generation && cell.g_cost <= g_cost {
+                continue;
+            }
+            self.cells[index] = Cell {
+                opened: self.generation,
+                g_cost,
+                parent,
+                ..cell
+            };
+            let h_cost = self.distance(pos, goal);
+            self.open.push(Node {
+                pos,
+                g_cost,
+                h_cost,
+                f_cost: self.priority.f_cost(g_cost, h_cost),
+                moves,
+            });
+            self.stats.generated += 1;
+            if let Some(step) = self.trace.as_mut().and_then(|trace| trace.last_mut()) {
+                step.opened.push(pos);
+            }
+        }
+        self.stats.track(self.open.len(), self.closed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BoundaryRule;
+    use crate::engine::{GameState, StepOutcome};
+    use crate::snake::Tail;
+    use rand::Rng;
+    use std::path::Path;
+
+    /// Length of the shortest path found by a plain breadth-first search.
+    fn bfs(
+        config: BoardConfig,
+        walls: &[Position],
+        start: Position,
+        goal: Position,
+    ) -> Option<usize> {
+        let mut dist = vec![None; config.cells()];
+        let mut queue = VecDeque::new();
+        dist[config.index(start)] = Some(0);
+        queue.push_back(start);
+        while let Some(pos) = queue.pop_front() {
+            let next_dist = dist[config.index(pos)].map(|d| d + 1);
+            if pos == goal {
+                return dist[config.index(pos)];
+            }
+            for direction in &Direction::ALL {
+                let next = config.wrap(pos.step(*direction));
+                if config.contains(next)
+                    && !walls.contains(&next)
+                    && dist[config.index(next)].is_none()
+                {
+                    dist[config.index(next)] = next_dist;
+                    queue.push_back(next);
                 }
             }
         }
         None
     }
 
-    fn backtrack_path(&self, current: Node) -> Vec<Position> {
-        let mut path = Vec::with_capacity(512);
-        path.push(current.pos);
-        let mut current = current;
-        while let Some(id) = current.parent_id {
-            path.push(current.pos);
-            unsafe {
-                current = *self.node_link.get_unchecked(id);
+    /// Runs A* and checks that its path is a walk from `start` to `goal`
+    /// around the walls, returning the number of moves.
+    fn a_star(
+        config: BoardConfig,
+        walls: &[Position],
+        start: Position,
+        goal: Position,
+    ) -> Option<usize> {
+        let mut searcher = Searcher::new(config);
+        searcher.set_walls(walls);
+        let path = searcher.a_star(start, goal, &Tail::new())?;
+        assert_eq!(path.cells.first(), Some(&start));
+        assert_eq!(path.cells.last(), Some(&goal));
+        assert_eq!(path.cost as usize, path.cells.len() - 1);
+        for pair in path.cells.windows(2) {
+            assert!(
+                config.direction_to(pair[0], pair[1]).is_some(),
+                "{:?} is not a step",
+                pair
+            );
+            assert!(
+                !walls.contains(&pair[1]),
+                "the path enters the wall {:?}",
+                pair[1]
+            );
+        }
+        Some(path.cells.len() - 1)
+    }
+
+    #[test]
+    fn path_starting_on_the_goal_has_no_steps() {
+        let config = BoardConfig::new(4, 4, 1);
+        let pos = Position { x: 2, y: 1 };
+        let path = Searcher::new(config)
+            .a_star(pos, pos, &Tail::new())
+            .unwrap();
+        assert_eq!(path.cells, vec![pos]);
+        assert_eq!(path.cost, 0);
+        assert_eq!(path.first_step(), None);
+    }
+
+    #[test]
+    fn path_leads_from_start_to_goal() {
+        let config = BoardConfig::new(5, 1, 1);
+        let cells = (0..5).map(|x| Position { x, y: 0 }).collect::<Vec<_>>();
+        let path = Searcher::new(config)
+            .a_star(cells[0], cells[4], &Tail::new())
+            .unwrap();
+        assert_eq!(path.cells, cells);
+        assert_eq!(path.cost, 4);
+        assert_eq!(path.first_step(), Some(cells[1]));
+    }
+
+    #[test]
+    fn stats_count_the_work_of_every_search() {
+        let config = BoardConfig::new(5, 1, 1);
+        let (start, goal) = (Position { x: 0, y: 0 }, Position { x: 4, y: 0 });
+        let mut searcher = Searcher::new(config);
+        searcher.a_star(start, goal, &Tail::new()).unwrap();
+        searcher.a_star(goal, start, &Tail::new()).unwrap();
+
+        let stats = searcher.stats();
+        assert_eq!(stats.searches, 1);
+        assert_eq!((stats.expanded, stats.generated), (5, 5));
+        assert_eq!((stats.peak_open, stats.peak_closed), (1, 4));
+        let totals = searcher.totals();
+        assert_eq!(totals.searches, 2);
+        assert_eq!((totals.expanded, totals.generated), (10, 10));
+        assert!(totals.duration >= stats.duration);
+    }
+
+    #[test]
+    fn trace_records_every_expansion() {
+        let level = Level::load(Path::new("maze.txt")).unwrap();
+        let config = BoardConfig::new(level.width, level.height, 1);
+        let (start, goal) = (level.start.unwrap(), level.goal.unwrap());
+        let mut searcher = Searcher::new(config);
+        searcher.set_walls(level.walls());
+        let trace = searcher.trace_a_star(start, goal, &Tail::new());
+
+        assert_eq!(trace.steps.len(), searcher.stats().expanded);
+        assert_eq!(trace.steps.first().map(|step| step.current), Some(start));
+        assert_eq!(trace.steps.last().map(|step| step.current), Some(goal));
+        assert_eq!(trace.path, searcher.a_star(start, goal, &Tail::new()));
+        let (open, closed) = trace.sets_after(trace.steps.len());
+        assert_eq!(closed.len(), trace.steps.len());
+        assert!(open.is_disjoint(&closed));
+        assert_eq!(
+            trace.sets_after(0),
+            (std::iter::once(start).collect(), HashSet::new())
+        );
+    }
+
+    #[test]
+    fn path_crosses_wrapping_edges() {
+        let mut config = BoardConfig::new(5, 5, 1);
+        config.boundary = BoundaryRule::Wrap;
+        let (start, goal) = (Position { x: 0, y: 2 }, Position { x: 4, y: 2 });
+        let path = Searcher::new(config)
+            .a_star(start, goal, &Tail::new())
+            .unwrap();
+        assert_eq!(path.cells, vec![start, goal]);
+        assert_eq!(path.first_step(), Some(goal));
+    }
+
+    #[test]
+    fn path_routes_around_obstacles() {
+        let config = BoardConfig::new(3, 3, 1);
+        let (start, goal) = (Position { x: 0, y: 0 }, Position { x: 2, y: 0 });
+        let body = [Position { x: 1, y: 0 }, Position { x: 1, y: 1 }];
+        let path = Searcher::new(config)
+            .a_star(start, goal, &body.iter().copied().collect())
+            .unwrap();
+        assert_eq!(path.cost, 6);
+        assert!(path.cells.iter().all(|pos| !body.contains(pos)));
+    }
+
+    #[test]
+    fn a_star_is_optimal_on_the_maze() {
+        let level = Level::load(Path::new("maze.txt")).unwrap();
+        let config = BoardConfig::new(level.width, level.height, 1);
+        let (start, goal) = (level.start.unwrap(), level.goal.unwrap());
+        let expected = bfs(config, level.walls(), start, goal);
+        assert!(expected.is_some());
+        assert_eq!(a_star(config, level.walls(), start, goal), expected);
+    }
+
+    #[test]
+    fn a_star_is_optimal_on_random_grids() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let mut config = BoardConfig::new(rng.gen_range(2, 30), rng.gen_range(2, 30), 1);
+            if rng.gen() {
+                config.boundary = BoundaryRule::Wrap;
             }
+            let cells = (0..config.cells()).map(|i| config.position(i));
+            let (walls, free): (Vec<_>, Vec<_>) = cells.partition(|_| rng.gen_bool(0.3));
+            if free.is_empty() {
+                continue;
+            }
+            let start = *free.choose(&mut rng).unwrap();
+            let goal = *free.choose(&mut rng).unwrap();
+            assert_eq!(
+                a_star(config, &walls, start, goal),
+                bfs(config, &walls, start, goal),
+                "{config:?} from {start:?} to {goal:?}"
+            );
         }
-        path
     }
 
-    fn new_gen_childs(&mut self, current: &Node) {
-        for i in 0..ROTATION_MATRIX.len() {
-            let (x, y) = ROTATION_MATRIX[i];
-            let x_offset = x * NODE_SIZE as isize;
-            let y_offset = y * NODE_SIZE as isize;
-            let pos = Position {
-                x: current.pos.x + x_offset,
-                y: current.pos.y + y_offset,
+    #[test]
+    fn reused_searcher_forgets_the_last_search() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let config = BoardConfig::new(12, 9, 1);
+        let mut reused = Searcher::new(config);
+        reused.generation = u32::MAX - 2;
+        reused.obstacle_generation = u32::MAX - 2;
+        for _ in 0..20 {
+            let cells = (0..config.cells()).map(|i| config.position(i));
+            let obstacles = cells.filter(|_| rng.gen_bool(0.3)).collect::<Tail>();
+            let start = config.position(rng.gen_range(0, config.cells()));
+            let goal = config.position(rng.gen_range(0, config.cells()));
+            let expected = Searcher::new(config).a_star(start, goal, &obstacles);
+            assert_eq!(
+                reused
+                    .a_star(start, goal, &obstacles)
+                    .map(|path| path.cells),
+                expected.map(|path| path.cells),
+                "from {start:?} to {goal:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn timed_search_waits_for_the_tail_to_move_on() {
+        let config = BoardConfig::new(4, 2, 1);
+        let cells = [(0, 0), (0, 1), (1, 1), (2, 1), (3, 1), (3, 0), (2, 0)];
+        let snake = Snake {
+            tail: cells.iter().map(|&(x, y)| Position { x, y }).collect(),
+            direction: Some(Direction::Up),
+        };
+        let head = snake.tail[0];
+        let mut searcher = Searcher::new(config);
+
+        let end = Position { x: 2, y: 0 };
+        assert_eq!(searcher.a_star(head, end, &snake.tail), None);
+        assert_eq!(searcher.a_star_timed(head, end, &snake).unwrap().cost, 2);
+
+        // (1, 1) is free after five moves, so the path has to go around.
+        let body = Position { x: 1, y: 1 };
+        let path = searcher.a_star_timed(head, body, &snake).unwrap();
+        assert_eq!(path.cost, 6);
+        assert_eq!(path.first_step(), Some(Position { x: 1, y: 0 }));
+    }
+
+    #[test]
+    fn timed_search_around_keeps_off_the_cell() {
+        let config = BoardConfig::new(4, 2, 1);
+        let cells = [(0, 0), (0, 1), (1, 1), (2, 1), (3, 1), (3, 0), (2, 0)];
+        let snake = Snake {
+            tail: cells.iter().map(|&(x, y)| Position { x, y }).collect(),
+            direction: Some(Direction::Up),
+        };
+        let (head, end) = (snake.tail[0], Position { x: 2, y: 0 });
+        let mut searcher = Searcher::new(config);
+
+        let avoid = Position { x: 1, y: 0 };
+        assert_eq!(searcher.a_star_timed_around(head, end, &snake, avoid), None);
+        let avoid = Position { x: 3, y: 0 };
+        let path = searcher
+            .a_star_timed_around(head, end, &snake, avoid)
+            .unwrap();
+        assert_eq!(path.cost, 2);
+    }
+
+    #[test]
+    fn lengthened_path_still_leads_to_the_goal() {
+        let config = BoardConfig::new(5, 4, 1);
+        let obstacles = [(2, 1), (2, 2)]
+            .iter()
+            .map(|&(x, y)| Position { x, y })
+            .collect::<Tail>();
+        let (start, goal) = (Position { x: 0, y: 0 }, Position { x: 4, y: 0 });
+        let mut searcher = Searcher::new(config);
+        let mut path = searcher.a_star(start, goal, &obstacles).unwrap();
+        assert_eq!(path.cost, 4);
+
+        searcher.lengthen(&mut path);
+        assert_eq!(path.cells.first(), Some(&start));
+        assert_eq!(path.cells.last(), Some(&goal));
+        assert_eq!(path.cost as usize, path.cells.len() - 1);
+        assert!(path.cost > 4, "{:?}", path.cells);
+        assert!(path
+            .cells
+            .windows(2)
+            .all(|step| config.direction_to(step[0], step[1]).is_some()));
+        let unique = path.cells.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), path.cells.len());
+        assert!(path.cells.iter().all(|pos| !obstacles.contains(pos)));
+    }
+
+    #[test]
+    fn zig_zag_cycles_cover_boards_with_an_even_side() {
+        for &(width, height) in &[(2, 2), (6, 4), (5, 4), (4, 5), (3, 2), (2, 7)] {
+            let config = BoardConfig::new(width, height, 1);
+            let solver = Solver::new(config, CycleKind::ZigZag).unwrap();
+            assert_eq!(solver.path.len(), config.cells(), "{width}x{height}");
+            solver.validate_path(&Level::empty(&config)).unwrap();
+        }
+    }
+
+    #[test]
+    fn zig_zag_cycles_need_an_even_side() {
+        let cases = [
+            (
+                (3, 3),
+                "zig-zag cycles need a board with an even side, got 3x3",
+            ),
+            (
+                (5, 7),
+                "zig-zag cycles need a board with an even side, got 5x7",
+            ),
+            ((1, 4), "there is no Hamiltonian cycle on a 1x4 board"),
+            ((6, 1), "there is no Hamiltonian cycle on a 6x1 board"),
+        ];
+        for &((width, height), message) in &cases {
+            let config = BoardConfig::new(width, height, 1);
+            let error = Solver::new(config, CycleKind::ZigZag).unwrap_err();
+            assert_eq!(error.to_string(), message);
+        }
+
+        // A torus has cycles on odd boards too, just not zig-zag ones.
+        let torus = BoardConfig {
+            boundary: BoundaryRule::Wrap,
+            ..BoardConfig::new(3, 3, 1)
+        };
+        let error = Solver::new(torus, CycleKind::ZigZag).unwrap_err();
+        assert_eq!(error.to_string(), cases[0].1);
+    }
+
+    #[test]
+    fn spanning_tree_cycles_depend_on_the_seed_alone() {
+        let config = BoardConfig::new(8, 6, 1);
+        let cycle = |seed| {
+            let solver = Solver::new(config, CycleKind::SpanningTree { seed }).unwrap();
+            solver.validate_path(&Level::empty(&config)).unwrap();
+            solver.path
+        };
+        assert_eq!(cycle(7), cycle(7));
+        let cycles = (0..8).map(cycle).collect::<HashSet<_>>();
+        assert_eq!(cycles.len(), 8);
+    }
+
+    #[test]
+    fn spanning_tree_cycles_need_even_sides() {
+        for &(width, height) in &[(5, 4), (4, 5), (3, 3)] {
+            let config = BoardConfig::new(width, height, 1);
+            let error = Solver::new(config, CycleKind::SpanningTree { seed: 1 }).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "random Hamiltonian cycles need an even width and height, got {width}x{height}"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn validation_rejects_cycles_through_walls_or_gaps() {
+        let level = Level::parse("|S| | | |\n| |#| | |\n| | | | |\n| | | | |\n").unwrap();
+        let config = BoardConfig::new(level.width, level.height, 1);
+        let mut solver = Solver::new(config, CycleKind::ZigZag).unwrap();
+        let wall = Position { x: 1, y: 1 };
+        let index = solver.get_ham_path_index(wall).unwrap();
+        assert_eq!(
+            solver.validate_path(&level).unwrap_err().to_string(),
+            format!("cycle cell {index} at {wall:?} is a wall")
+        );
+
+        let level = Level::empty(&config);
+        // The cycle starts at (0, 2) and runs up the first column.
+        solver.path.swap(0, 2);
+        assert_eq!(
+            solver.validate_path(&level).unwrap_err().to_string(),
+            "cycle cells Position { x: 0, y: 2 } and Position { x: 1, y: 0 } are not adjacent"
+        );
+    }
+
+    #[test]
+    fn repaired_cycle_stays_valid_and_brings_the_apple_closer() {
+        for &(width, height, seed) in &[(4, 4, 1), (6, 6, 2), (8, 6, 3), (10, 10, 4), (12, 10, 5)] {
+            let config = BoardConfig::new(width, height, 1);
+            let mut state = GameState::new(config, Level::empty(&config), seed);
+            let mut solver = Solver::new(config, CycleKind::ZigZag).unwrap();
+            let mut moves = 0;
+            let outcome = loop {
+                assert!(
+                    moves < config.cells() * config.cells(),
+                    "the snake goes in circles"
+                );
+                let view = state.view();
+                solver.orient_cycle(&view);
+                let dist = solver.cycle_dist(view.head(), view.apple).unwrap();
+                let in_order = solver.is_in_cycle_order(view.snake);
+
+                solver.repair_cycle(&view);
+                solver.validate_path(view.level).unwrap();
+                let repaired = solver.cycle_dist(view.head(), view.apple).unwrap();
+                assert!(
+                    repaired <= dist,
+                    "{} moves to the apple, {} before",
+                    repaired,
+                    dist
+                );
+                assert!(!in_order || solver.is_in_cycle_order(view.snake));
+                // Checking only the cells freed up since the last move must
+                // not miss anything a full search finds.
+                let head = solver.get_ham_path_index(view.head()).unwrap();
+                let body = solver.body_ahead(&view);
+                assert!(solver.best_splice(&view, head, body, 0).is_none());
+
+                let direction = solver
+                    .make_move(&view)
+                    .or_else(|| view.safe_directions().next());
+                match state.step(direction) {
+                    StepOutcome::Moved | StepOutcome::AteApple => moves += 1,
+                    outcome => break outcome,
+                }
             };
+            assert_eq!(outcome, StepOutcome::Won);
+        }
+    }
 
-            let child_node = Node {
-                pos,
-                parent_id: Some(current.id),
-                id: self.gen_id(),
-                g_cost: 0,
-                h_cost: 0,
-                f_cost: 0,
+    /// Checks the index table against a linear search of the cycle.
+    fn assert_indexed(solver: &Solver) {
+        for index in 0..solver.config.cells() {
+            let pos = solver.config.position(index);
+            let expected = solver.path.iter().position(|cell| *cell == pos);
+            assert_eq!(solver.get_ham_path_index(pos), expected, "{pos:?}");
+        }
+    }
+
+    #[test]
+    fn cycle_dist_counts_the_moves_along_the_cycle() {
+        let config = BoardConfig::new(4, 4, 1);
+        let solver = Solver::new(config, CycleKind::ZigZag).unwrap();
+        let path = &solver.path;
+        let len = path.len();
+        assert_indexed(&solver);
+        for (i, from) in path.iter().enumerate() {
+            for (j, to) in path.iter().enumerate() {
+                assert_eq!(solver.cycle_dist(*from, *to), Some((j + len - i) % len));
+            }
+        }
+        assert_eq!(solver.cycle_dist(path[len - 1], path[0]), Some(1));
+        assert_eq!(solver.cycle_dist(path[len - 2], path[1]), Some(3));
+
+        for off in [Position { x: -1, y: 0 }, Position { x: 4, y: 2 }] {
+            assert_eq!(solver.cycle_dist(off, path[0]), None);
+            assert_eq!(solver.cycle_dist(path[0], off), None);
+        }
+    }
+
+    #[test]
+    fn rebuilt_cycle_index_matches_the_cycle() {
+        // A snake lying on the cycle against its direction gets it reversed.
+        let config = BoardConfig::new(4, 4, 1);
+        let level = Level::empty(&config);
+        let mut solver = Solver::new(config, CycleKind::ZigZag).unwrap();
+        let forward = solver.path.clone();
+        let snake = Snake {
+            tail: forward[4..12].iter().copied().collect(),
+            direction: config.direction_to(forward[5], forward[4]),
+        };
+        let view = GameView {
+            config: &config,
+            level: &level,
+            snake: &snake,
+            apple: forward[14],
+            seed: 0,
+        };
+        solver.orient_cycle(&view);
+        assert_eq!(
+            solver.path,
+            forward.iter().rev().copied().collect::<Vec<_>>()
+        );
+        assert_indexed(&solver);
+
+        let config = BoardConfig::new(6, 6, 1);
+        let mut state = GameState::new(config, Level::empty(&config), 2);
+        let mut solver = Solver::new(config, CycleKind::ZigZag).unwrap();
+        let mut repaired = false;
+        for _ in 0..config.cells() * 4 {
+            let view = state.view();
+            solver.orient_cycle(&view);
+            let before = solver.path.clone();
+            solver.repair_cycle(&view);
+            repaired |= solver.path != before;
+            assert_indexed(&solver);
+
+            let direction = solver
+                .make_move(&view)
+                .or_else(|| view.safe_directions().next());
+            if !matches!(
+                state.step(direction),
+                StepOutcome::Moved | StepOutcome::AteApple
+            ) {
+                break;
+            }
+        }
+        assert!(repaired);
+    }
+
+    #[test]
+    fn reused_searcher_forgets_the_last_fill() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let config = BoardConfig::new(12, 9, 1);
+        let level = Level::empty(&config);
+        let mut reused = Searcher::new(config);
+        reused.fill_generation = u32::MAX - 2;
+        for _ in 0..20 {
+            let cells = (0..config.cells()).map(|i| config.position(i));
+            let snake = Snake {
+                tail: cells.filter(|_| rng.gen_bool(0.4)).collect(),
+                direction: None,
             };
+            let view = GameView {
+                config: &config,
+                level: &level,
+                snake: &snake,
+                apple: Position::default(),
+                seed: 0,
+            };
+            let from = config.position(rng.gen_range(0, config.cells()));
+            assert_eq!(
+                reused.reachable_area(&view, from),
+                Searcher::new(config).reachable_area(&view, from)
+            );
+            assert_eq!(reused.regions(&view), Searcher::new(config).regions(&view));
+        }
+    }
+
+    #[test]
+    fn regions_are_split_by_the_snake() {
+        let config = BoardConfig::new(5, 3, 1);
+        let level = Level::empty(&config);
+        let snake = Snake {
+            tail: (0..3).map(|y| Position { x: 1, y }).collect(),
+            direction: Some(Direction::Up),
+        };
+        let view = GameView {
+            config: &config,
+            level: &level,
+            snake: &snake,
+            apple: Position { x: 4, y: 2 },
+            seed: 0,
+        };
+        let mut searcher = Searcher::new(config);
 
-            self.childs[i] = child_node;
-            self.node_link.push(child_node);
+        let regions = searcher.regions(&view);
+        let mut sizes = regions.sizes.clone();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![3, 9]);
+        assert_eq!(regions.size_at(&config, Position { x: 1, y: 1 }), 0);
+        assert_eq!(searcher.reachable_area(&view, Position { x: 0, y: 1 }), 3);
+        assert_eq!(searcher.reachable_area(&view, Position { x: 4, y: 0 }), 9);
+    }
+}
+
+#[cfg(test)]
+mod benches {
+    extern crate test;
+
+    use super::*;
+    use crate::snake::Tail;
+    use std::path::Path;
+    use test::Bencher;
+
+    /// A snake lying in rows across the left half of a 32x32 board with its
+    /// head in the top right corner of them.
+    fn coiled_snake() -> Snake {
+        let mut tail = VecDeque::new();
+        for y in 0..32 {
+            for i in 0..16 {
+                let x = if y % 2 == 0 { 15 - i } else { i };
+                tail.push_back(Position { x, y });
+            }
         }
+        Snake {
+            tail,
+            direction: Some(Direction::Right),
+        }
+    }
+
+    #[bench]
+    fn a_star_on_the_maze(b: &mut Bencher) {
+        let level = Level::load(Path::new("maze.txt")).unwrap();
+        let config = BoardConfig::new(level.width, level.height, 1);
+        let (start, goal) = (level.start.unwrap(), level.goal.unwrap());
+        let mut searcher = Searcher::new(config);
+        searcher.set_walls(level.walls());
+        let obstacles = Tail::new();
+        b.iter(|| searcher.a_star(start, goal, &obstacles));
+    }
+
+    #[bench]
+    fn a_star_across_an_open_board(b: &mut Bencher) {
+        let config = BoardConfig::new(64, 64, 1);
+        let (start, goal) = (Position { x: 0, y: 63 }, Position { x: 63, y: 0 });
+        let mut searcher = Searcher::new(config);
+        let obstacles = Tail::new();
+        b.iter(|| searcher.a_star(start, goal, &obstacles));
     }
 
-    fn gen_id(&mut self) -> usize {
-        let id = self.cursor;
-        self.cursor += 1;
-        id
+    #[bench]
+    fn a_star_timed_past_a_coiled_snake(b: &mut Bencher) {
+        let config = BoardConfig::new(32, 32, 1);
+        let snake = coiled_snake();
+        let goal = Position { x: 0, y: 31 };
+        let mut searcher = Searcher::new(config);
+        b.iter(|| searcher.a_star_timed(snake.tail[0], goal, &snake));
     }
 }
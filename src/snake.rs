@@ -1,5 +1,5 @@
-use crate::game::{Direction, NODE_SIZE, NODE_SIZE_F32, WINDOW_SIZE_X, WINDOW_SIZE_Y};
-use coffee::graphics::{Color, Mesh, Rectangle, Shape};
+use crate::board::{BoardConfig, BoundaryRule};
+use crate::engine::Direction;
 use rand::Rng;
 use std::collections::VecDeque;
 
@@ -7,19 +7,18 @@ const START_SNAKE_LEN: usize = 6;
 
 pub type Tail = VecDeque<Position>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Snake {
     pub tail: Tail,
     pub direction: Option<Direction>,
 }
 
 impl Snake {
-    pub fn new() -> Self {
-        let mut tail = (1..START_SNAKE_LEN)
-            .into_iter()
+    pub fn new(config: &BoardConfig) -> Self {
+        let mut tail = (1..START_SNAKE_LEN.min(config.width))
             .map(|i| Position {
-                x: (i * NODE_SIZE) as isize,
-                y: 2 * NODE_SIZE as isize,
+                x: i as isize,
+                y: 2.min(config.height - 1) as isize,
             })
             .collect::<VecDeque<_>>();
         tail.reserve(256);
@@ -29,6 +28,17 @@ impl Snake {
         }
     }
 
+    /// A snake that starts out as a single node at `start` and grows to its
+    /// full starting length during the first moves.
+    pub fn at(start: Position) -> Self {
+        let mut tail = VecDeque::with_capacity(256);
+        tail.push_back(start);
+        Self {
+            tail,
+            direction: None,
+        }
+    }
+
     pub fn ate_itself(&self) -> bool {
         let head = self.head().unwrap();
         self.is_inside(*head)
@@ -47,41 +57,17 @@ impl Snake {
             self.tail.pop_back();
         }
 
-        let head = self.head().cloned().unwrap();
-        match self.direction {
-            Some(Direction::Up) => {
-                self.tail.push_front(Position {
-                    x: head.x,
-                    y: head.y - NODE_SIZE as isize,
-                });
-            }
-            Some(Direction::Down) => self.tail.push_front(Position {
-                x: head.x,
-                y: head.y + NODE_SIZE as isize,
-            }),
-            Some(Direction::Left) => self.tail.push_front(Position {
-                x: head.x - NODE_SIZE as isize,
-                y: head.y,
-            }),
-            Some(Direction::Right) => self.tail.push_front(Position {
-                x: head.x + NODE_SIZE as isize,
-                y: head.y,
-            }),
-            None => {}
-        };
-        //self.teleport_if_outside()
+        let head = self.head().copied().unwrap();
+        if let Some(direction) = self.direction {
+            self.tail.push_front(head.step(direction));
+        }
     }
 
-    #[allow(dead_code)]
-    fn teleport_if_outside(&mut self) {
+    /// Moves a head that just left the board to the opposite edge, see
+    /// `BoundaryRule::Wrap`.
+    pub fn teleport_if_outside(&mut self, config: &BoardConfig) {
         let head = self.tail.front_mut().unwrap();
-        match self.direction {
-            Some(Direction::Down) if head.y > WINDOW_SIZE_Y as isize => head.y = 0,
-            Some(Direction::Right) if head.x > WINDOW_SIZE_X as isize => head.x = 0,
-            Some(Direction::Up) if head.y < 0 => head.y = WINDOW_SIZE_Y as isize,
-            Some(Direction::Left) if head.x < 0 => head.x = WINDOW_SIZE_X as isize,
-            _ => {}
-        }
+        *head = config.wrap(*head);
     }
 
     pub fn direction_is_legal(&self, direction: Direction) -> bool {
@@ -97,8 +83,52 @@ impl Snake {
         }
     }
 
-    pub fn add_node(&mut self, pos: Position) {
-        self.tail.push_front(pos)
+    /// Grows the snake by one node. The end of the tail stays on its cell for
+    /// one more tick instead of following the rest of the body.
+    pub fn grow(&mut self) {
+        let end = *self.tail.back().unwrap();
+        self.tail.push_back(end);
+    }
+
+    /// Number of moves the snake keeps growing without eating, until it
+    /// reaches its full starting length.
+    pub fn growth(&self) -> usize {
+        (START_SNAKE_LEN + 1).saturating_sub(self.tail.len())
+    }
+
+    /// Whether the end of the tail leaves its cell on the next move, which it
+    /// doesn't while the snake grows.
+    pub fn end_moves_on(&self) -> bool {
+        let len = self.tail.len();
+        len > START_SNAKE_LEN && self.tail[len - 1] != self.tail[len - 2]
+    }
+
+    /// Every node of the tail with the number of moves after which it has
+    /// left its cell, assuming the snake doesn't eat on the way. The tail
+    /// stays put while the snake still grows to its starting length.
+    pub fn vacated_after(&self) -> impl Iterator<Item = (Position, usize)> + '_ {
+        let len = self.tail.len();
+        let growth = self.growth();
+        self.tail
+            .iter()
+            .enumerate()
+            .map(move |(i, pos)| (*pos, len - i + growth))
+    }
+
+    /// The snake after walking `cells`, which start at its head, with the
+    /// same moves as `GameState::step`.
+    pub fn walk(&self, cells: &[Position], config: &BoardConfig) -> Self {
+        let mut snake = self.clone();
+        for step in cells.windows(2) {
+            if let Some(direction) = config.direction_to(step[0], step[1]) {
+                snake.set_direction(direction);
+            }
+            snake.update();
+            if config.boundary == BoundaryRule::Wrap {
+                snake.teleport_if_outside(config);
+            }
+        }
+        snake
     }
 
     pub fn head(&self) -> Option<&Position> {
@@ -124,6 +154,45 @@ impl Position {
         isize::abs(dx) + isize::abs(dy)
     }
 
+    /// Manhattan distance on a torus of size `bounds`, where every axis may
+    /// also be crossed the other way round over the edge.
+    pub const fn wrapped_mhtn_dist(self, rhs: Self, bounds: Self) -> isize {
+        let dx = isize::abs(rhs.x - self.x);
+        let dy = isize::abs(rhs.y - self.y);
+        let dx = if dx * 2 > bounds.x { bounds.x - dx } else { dx };
+        let dy = if dy * 2 > bounds.y { bounds.y - dy } else { dy };
+        dx + dy
+    }
+
+    /// Number of king moves between the two cells, diagonal ones included.
+    pub const fn chebyshev_dist(self, rhs: Self) -> isize {
+        let dx = isize::abs(rhs.x - self.x);
+        let dy = isize::abs(rhs.y - self.y);
+        if dx > dy {
+            dx
+        } else {
+            dy
+        }
+    }
+
+    /// Cost of the cheapest king walk between the two cells when a straight
+    /// step costs `straight` and a diagonal one `diagonal`.
+    pub const fn octile_dist(self, rhs: Self, straight: isize, diagonal: isize) -> isize {
+        let dx = isize::abs(rhs.x - self.x);
+        let dy = isize::abs(rhs.y - self.y);
+        let (short, long) = if dx < dy { (dx, dy) } else { (dy, dx) };
+        diagonal * short + straight * (long - short)
+    }
+
+    /// Number of steps between the two cells on a hex grid whose odd rows are
+    /// shifted half a cell to the right.
+    pub const fn hex_dist(self, rhs: Self) -> isize {
+        // Axial coordinates, the third cube coordinate is `-q - r`.
+        let dq = (rhs.x - (rhs.y - (rhs.y & 1)) / 2) - (self.x - (self.y - (self.y & 1)) / 2);
+        let dr = rhs.y - self.y;
+        (isize::abs(dq) + isize::abs(dr) + isize::abs(dq + dr)) / 2
+    }
+
     #[allow(dead_code)]
     pub fn dist(self, rhs: Self) -> f32 {
         let dx = (self.x - rhs.x) as f32;
@@ -135,15 +204,11 @@ impl Position {
         self.x >= lower.x && self.x < upper.x && self.y >= lower.y && self.y < upper.y
     }
 
-    pub fn to_direction(start: Position, end: Position) -> Option<Direction> {
-        let dx = ((end.x - start.x) as f32 * (1.0 / NODE_SIZE_F32)) as i8;
-        let dy = ((end.y - start.y) as f32 * (1.0 / NODE_SIZE_F32)) as i8;
-        match (dx, dy) {
-            (-1, 0) => Some(Direction::Left),
-            (1, 0) => Some(Direction::Right),
-            (0, 1) => Some(Direction::Down),
-            (0, -1) => Some(Direction::Up),
-            _ => None,
+    pub const fn step(self, direction: Direction) -> Self {
+        let (dx, dy) = direction.offset();
+        Self {
+            x: self.x + dx,
+            y: self.y + dy,
         }
     }
 }
@@ -162,32 +227,34 @@ pub struct Apple {
 }
 
 impl Apple {
-    pub fn new() -> Self {
-        Self {
-            pos: Self::rand_pos(),
-            eaten: false,
+    /// Places an apple on a cell picked uniformly among the ones neither
+    /// `snake` nor `walls` cover. Returns `None` once the board is full.
+    pub fn spawn<R: Rng>(
+        config: &BoardConfig,
+        snake: &Snake,
+        walls: &[Position],
+        rng: &mut R,
+    ) -> Option<Self> {
+        let mut occupied = vec![false; config.cells()];
+        let blocked = snake.tail.iter().chain(walls);
+        for node in blocked.filter(|node| config.contains(**node)) {
+            occupied[config.index(*node)] = true;
         }
-    }
 
-    fn rand_pos() -> Position {
-        let mut rng = rand::thread_rng();
-        let upper_x_bound = WINDOW_SIZE_X / NODE_SIZE;
-        let upper_y_bound = WINDOW_SIZE_Y / NODE_SIZE;
-        Position {
-            x: (NODE_SIZE * rng.gen_range(0, upper_x_bound)) as isize,
-            y: (NODE_SIZE * rng.gen_range(0, upper_y_bound)) as isize,
+        let free = occupied.iter().filter(|cell| !**cell).count();
+        if free == 0 {
+            return None;
         }
-    }
-
-    pub fn draw(&self, mesh: &mut Mesh) {
-        mesh.fill(
-            Shape::Rectangle(Rectangle {
-                x: self.pos.x as f32,
-                y: self.pos.y as f32,
-                width: NODE_SIZE_F32,
-                height: NODE_SIZE_F32,
-            }),
-            Color::RED,
-        );
+        let nth = rng.gen_range(0, free);
+        let index = occupied
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| !**cell)
+            .nth(nth)
+            .map(|(index, _)| index)?;
+        Some(Self {
+            pos: config.position(index),
+            eaten: false,
+        })
     }
 }
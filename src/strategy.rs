@@ -0,0 +1,457 @@
+use crate::board::BoardConfig;
+use crate::engine::{Direction, GameView};
+use crate::search::{CycleKind, SearchStats, Searcher, Solver};
+use crate::snake::{Position, Snake};
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::fmt::Debug;
+
+/// A computer player. It is asked for a direction once per tick and has to
+/// answer with one, even if every option is fatal.
+pub trait Strategy: Debug {
+    fn next_direction(&mut self, view: &GameView) -> Direction;
+
+    /// The Hamiltonian cycle the strategy follows, if any, for `DrawMode::Path`.
+    fn cycle(&self) -> Option<&[Position]> {
+        None
+    }
+
+    /// Work done by the path searches of the strategy so far, `None` if it
+    /// doesn't search for paths.
+    fn search_stats(&self) -> Option<SearchStats> {
+        None
+    }
+}
+
+/// `Hybrid` hands over to the cycle once the snake covers this share of the
+/// board, given as a divisor. The longer the body grows outside of cycle order
+/// the less likely it ever fits onto the cycle.
+const HYBRID_HANDOVER_SHARE: usize = 8;
+
+type Constructor = fn(&GameView) -> Result<Box<dyn Strategy>>;
+
+/// Every computer player known to the game, in the order the `C` key cycles
+/// through them. The names are the ones accepted by `--strategy`.
+pub const STRATEGIES: [(&str, Constructor); 7] = [
+    ("hamiltonian", |view| {
+        Ok(Box::new(hamiltonian(view, CycleKind::ZigZag)?))
+    }),
+    ("hamiltonian-tree", |view| {
+        let cycle = CycleKind::SpanningTree { seed: view.seed };
+        Ok(Box::new(hamiltonian(view, cycle)?))
+    }),
+    ("hamiltonian-dynamic", |view| {
+        let mut solver = hamiltonian(view, CycleKind::ZigZag)?;
+        solver.repair = true;
+        Ok(Box::new(solver))
+    }),
+    ("greedy", |view| Ok(Box::new(Greedy::new(view)))),
+    ("safe-greedy", |view| Ok(Box::new(SafeGreedy::new(view)))),
+    ("hybrid", |view| Ok(Box::new(Hybrid::new(view)?))),
+    ("random-safe", |view| Ok(Box::new(RandomSafe::new(view)))),
+];
+
+/// Index of the strategy called `name` in `STRATEGIES`.
+pub fn find(name: &str) -> Option<usize> {
+    STRATEGIES.iter().position(|(n, _)| *n == name)
+}
+
+pub const fn name(index: usize) -> &'static str {
+    STRATEGIES[index].0
+}
+
+/// Builds the strategy at `index`, which fails if it can't play on the board
+/// of `view`, e.g. because there is no Hamiltonian cycle.
+pub fn create(index: usize, view: &GameView) -> Result<Box<dyn Strategy>> {
+    (STRATEGIES[index].1)(view)
+}
+
+/// Last resort if a strategy has no plan: any move that survives the next
+/// tick, otherwise keep going and hope for the best.
+fn fallback(view: &GameView) -> Direction {
+    view.safe_directions()
+        .next()
+        .or(view.snake.direction)
+        .unwrap_or(Direction::Up)
+}
+
+/// The safe move into the largest area, which keeps the snake alive for as
+/// long as possible if every move traps it.
+fn roomiest(searcher: &mut Searcher, view: &GameView) -> Option<Direction> {
+    let head = view.head();
+    view.safe_directions().max_by_key(|direction| {
+        searcher.reachable_area(view, view.config.wrap(head.step(*direction)))
+    })
+}
+
+fn hamiltonian(view: &GameView, cycle: CycleKind) -> Result<Solver> {
+    let mut solver = Solver::new(*view.config, cycle)?;
+    solver.searcher.set_walls(view.level.walls());
+    solver.validate_path(view.level)?;
+    Ok(solver)
+}
+
+impl Strategy for Solver {
+    fn next_direction(&mut self, view: &GameView) -> Direction {
+        self.orient_cycle(view);
+        if self.repair {
+            self.repair_cycle(view);
+        }
+        self.make_move(view)
+            .filter(|direction| view.safe_directions().any(|safe| safe == *direction))
+            .or_else(|| roomiest(&mut self.searcher, view))
+            .unwrap_or_else(|| fallback(view))
+    }
+
+    fn cycle(&self) -> Option<&[Position]> {
+        Some(&self.path)
+    }
+}
+
+/// Walks the shortest path to the apple, counting on the tail to move out of
+/// the way. The only thing it looks out for is not to crawl into a pocket too
+/// small for the snake.
+#[derive(Debug)]
+pub struct Greedy {
+    searcher: Searcher,
+}
+
+impl Greedy {
+    pub fn new(view: &GameView) -> Self {
+        let mut searcher = Searcher::new(*view.config);
+        searcher.set_walls(view.level.walls());
+        Self { searcher }
+    }
+}
+
+impl Strategy for Greedy {
+    fn next_direction(&mut self, view: &GameView) -> Direction {
+        let head = view.head();
+        self.searcher
+            .a_star_timed(head, view.apple, view.snake)
+            .and_then(|path| path.first_step())
+            .filter(|step| self.searcher.reachable_area(view, *step) >= view.snake.tail.len())
+            .and_then(|step| view.config.direction_to(head, step))
+            .or_else(|| roomiest(&mut self.searcher, view))
+            .unwrap_or_else(|| fallback(view))
+    }
+
+    fn search_stats(&self) -> Option<SearchStats> {
+        Some(*self.searcher.totals())
+    }
+}
+
+/// Like `Greedy`, but only takes safe paths to the apple.
+///
+/// A path is safe if a virtual snake that walked it could still reach its own
+/// tail afterwards. Until there is one, the snake follows its tail the long
+/// way round.
+#[derive(Debug)]
+pub struct SafeGreedy {
+    searcher: Searcher,
+}
+
+impl SafeGreedy {
+    pub fn new(view: &GameView) -> Self {
+        let mut searcher = Searcher::new(*view.config);
+        searcher.set_walls(view.level.walls());
+        Self { searcher }
+    }
+
+    /// Length of a long path from the head of `snake` to the end of its tail,
+    /// around `apple` if possible. Otherwise the snake grows on the apple and
+    /// the rest of the way has to work out with the longer snake.
+    fn tail_dist(
+        &mut self,
+        snake: &Snake,
+        apple: Option<Position>,
+        config: &BoardConfig,
+    ) -> Option<isize> {
+        let (head, end) = (snake.tail[0], snake.tail[snake.tail.len() - 1]);
+        if head == end {
+            return None;
+        }
+        let around = match apple {
+            Some(apple) => self.searcher.a_star_timed_around(head, end, snake, apple),
+            None => self.searcher.a_star_timed(head, end, snake),
+        };
+        if let Some(mut path) = around {
+            self.searcher.lengthen(&mut path);
+            return Some(path.cost);
+        }
+        let apple = apple?;
+        let path = self.searcher.a_star_timed(head, end, snake)?;
+        let at = path.cells.iter().position(|pos| *pos == apple)?;
+        let mut snake = snake.walk(&path.cells[..=at], config);
+        snake.grow();
+        Some(at as isize + self.tail_dist(&snake, None, config)?)
+    }
+
+    /// Takes the way to the apple if the tail can be reached from there, and
+    /// from the first step of the way, so the snake can still fall back on
+    /// its tail if the way changes later on.
+    fn eat(&mut self, view: &GameView) -> Option<Direction> {
+        let head = view.head();
+        let path = self.searcher.a_star_timed(head, view.apple, view.snake)?;
+        let mut snake = view.snake.walk(&path.cells, view.config);
+        snake.grow();
+        self.tail_dist(&snake, None, view.config)?;
+        let direction = view.config.direction_to(head, path.first_step()?)?;
+        self.tail_dist_after(view, direction)?;
+        Some(direction)
+    }
+
+    /// The move with the longest path back to the tail, so the snake takes
+    /// the long way round and leaves room behind it instead of hugging its
+    /// tail.
+    fn follow_tail(&mut self, view: &GameView) -> Option<Direction> {
+        Direction::ALL
+            .iter()
+            .copied()
+            .filter(|direction| view.snake.direction_is_legal(*direction))
+            .filter_map(|direction| Some((direction, self.tail_dist_after(view, direction)?)))
+            .max_by_key(|(_, dist)| *dist)
+            .map(|(direction, _)| direction)
+    }
+
+    /// `tail_dist` after moving in `direction`, `None` if the move loses the
+    /// tail. Moving onto the end of the tail is fine, it moves on in time.
+    fn tail_dist_after(&mut self, view: &GameView, direction: Direction) -> Option<isize> {
+        let head = view.head();
+        let step = view.config.wrap(head.step(direction));
+        if !view.config.contains(step) || view.level.is_wall(step) {
+            return None;
+        }
+        let mut snake = view.snake.walk(&[head, step], view.config);
+        if snake.ate_itself() {
+            return None;
+        }
+        let apple = if step == view.apple {
+            snake.grow();
+            None
+        } else {
+            Some(view.apple)
+        };
+        self.tail_dist(&snake, apple, view.config)
+    }
+}
+
+impl Strategy for SafeGreedy {
+    fn next_direction(&mut self, view: &GameView) -> Direction {
+        self.eat(view)
+            .or_else(|| self.follow_tail(view))
+            .or_else(|| roomiest(&mut self.searcher, view))
+            .unwrap_or_else(|| fallback(view))
+    }
+
+    fn search_stats(&self) -> Option<SearchStats> {
+        Some(*self.searcher.totals())
+    }
+}
+
+/// Plays safe-greedy while the snake is short and follows the Hamiltonian
+/// cycle later on.
+///
+/// It switches to the cycle once the snake covers an eighth of the board. The
+/// body is in no particular order by then, so it keeps playing safe-greedy
+/// until the solver is able to follow the cycle without running into the
+/// body, see `Solver::can_follow_cycle`.
+#[derive(Debug)]
+pub struct Hybrid {
+    safe_greedy: SafeGreedy,
+    solver: Solver,
+    /// Whether the solver took over for the rest of the game.
+    on_cycle: bool,
+}
+
+impl Hybrid {
+    pub fn new(view: &GameView) -> Result<Self> {
+        Ok(Self {
+            safe_greedy: SafeGreedy::new(view),
+            solver: hamiltonian(view, CycleKind::ZigZag)?,
+            on_cycle: false,
+        })
+    }
+}
+
+impl Strategy for Hybrid {
+    fn next_direction(&mut self, view: &GameView) -> Direction {
+        let long = view.snake.tail.len() * HYBRID_HANDOVER_SHARE >= view.config.cells();
+        if !self.on_cycle && long {
+            self.solver.orient_cycle(view);
+            self.on_cycle = self.solver.can_follow_cycle(view);
+        }
+        if self.on_cycle {
+            self.solver.next_direction(view)
+        } else {
+            self.safe_greedy.next_direction(view)
+        }
+    }
+
+    fn cycle(&self) -> Option<&[Position]> {
+        self.solver.cycle()
+    }
+
+    fn search_stats(&self) -> Option<SearchStats> {
+        Some(*self.safe_greedy.searcher.totals())
+    }
+}
+
+/// Wanders around by picking any move that survives the next tick.
+#[derive(Debug)]
+pub struct RandomSafe {
+    rng: StdRng,
+}
+
+impl RandomSafe {
+    pub fn new(view: &GameView) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(view.seed),
+        }
+    }
+}
+
+impl Strategy for RandomSafe {
+    fn next_direction(&mut self, view: &GameView) -> Direction {
+        let safe = view.safe_directions().collect::<Vec<_>>();
+        safe.choose(&mut self.rng)
+            .copied()
+            .unwrap_or_else(|| fallback(view))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{GameState, StepOutcome};
+    use crate::headless;
+    use crate::level::Level;
+
+    /// Plays a game of the strategy called `name` on an empty board and
+    /// returns how it ended, `None` if it stalled.
+    fn play(name: &str, width: usize, height: usize, seed: u64) -> Option<StepOutcome> {
+        let config = BoardConfig::new(width, height, 1);
+        let mut state = GameState::new(config, Level::empty(&config), seed);
+        let mut player = create(find(name).unwrap(), &state.view()).unwrap();
+        headless::play(&mut state, player.as_mut()).1
+    }
+
+    fn snake(cells: &[(isize, isize)], direction: Direction) -> Snake {
+        Snake {
+            tail: cells.iter().map(|&(x, y)| Position { x, y }).collect(),
+            direction: Some(direction),
+        }
+    }
+
+    #[test]
+    fn safe_greedy_follows_its_tail_the_long_way() {
+        let config = BoardConfig::new(4, 4, 1);
+        let level = Level::empty(&config);
+        let cells = [(0, 1), (0, 2), (0, 3), (1, 3), (2, 3), (2, 2), (1, 2)];
+        let snake = snake(&cells, Direction::Up);
+        let view = GameView {
+            config: &config,
+            level: &level,
+            snake: &snake,
+            apple: Position { x: 3, y: 3 },
+            seed: 0,
+        };
+        let mut player = SafeGreedy::new(&view);
+
+        // Right steps next to the end of the tail, Up leaves room behind.
+        let up = player.tail_dist_after(&view, Direction::Up).unwrap();
+        let right = player.tail_dist_after(&view, Direction::Right).unwrap();
+        assert!(up > right, "up {}, right {}", up, right);
+        assert_eq!(player.follow_tail(&view), Some(Direction::Up));
+    }
+
+    #[test]
+    fn safe_greedy_takes_the_roomiest_move_once_the_tail_is_lost() {
+        let level = Level::parse(" #   #\n   S #\n #   #\n #   #\n #   #\n").unwrap();
+        let config = BoardConfig::new(level.width, level.height, 1);
+        let snake = snake(&[(1, 1), (0, 1)], Direction::Right);
+        let view = GameView {
+            config: &config,
+            level: &level,
+            snake: &snake,
+            apple: Position { x: 1, y: 4 },
+            seed: 0,
+        };
+        let mut player = SafeGreedy::new(&view);
+
+        // Up is a dead end of a single cell, down a corridor of three.
+        assert_eq!(fallback(&view), Direction::Up);
+        assert_eq!(player.follow_tail(&view), None);
+        assert_eq!(player.next_direction(&view), Direction::Down);
+    }
+
+    #[test]
+    fn hybrid_switches_to_the_cycle_once_it_is_safe() {
+        let config = BoardConfig::new(4, 4, 1);
+        let level = Level::empty(&config);
+        let view = |snake, (x, y)| GameView {
+            config: &config,
+            level: &level,
+            snake,
+            apple: Position { x, y },
+            seed: 0,
+        };
+
+        // Across the cycle, following it either way runs into the body.
+        let cells = [
+            (0, 1),
+            (0, 2),
+            (1, 2),
+            (1, 1),
+            (2, 1),
+            (2, 2),
+            (3, 2),
+            (3, 1),
+        ];
+        let across = snake(&cells, Direction::Up);
+        let across = view(&across, (0, 0));
+        let mut player = Hybrid::new(&across).unwrap();
+        let expected = SafeGreedy::new(&across).next_direction(&across);
+        assert_eq!(player.next_direction(&across), expected);
+        assert!(!player.on_cycle);
+
+        let cells = [
+            (2, 1),
+            (2, 2),
+            (1, 2),
+            (1, 1),
+            (1, 0),
+            (0, 0),
+            (0, 1),
+            (0, 2),
+        ];
+        let along = snake(&cells, Direction::Up);
+        let along = view(&along, (3, 3));
+        let mut player = Hybrid::new(&along).unwrap();
+        assert_eq!(player.next_direction(&along), Direction::Up);
+        assert!(player.on_cycle);
+    }
+
+    #[test]
+    fn hybrid_beats_greedy() {
+        for seed in 0..3 {
+            assert_eq!(play("hybrid", 10, 10, seed), Some(StepOutcome::Won));
+            assert_eq!(play("greedy", 10, 10, seed), Some(StepOutcome::Died));
+        }
+    }
+
+    #[test]
+    fn hamiltonian_wins_on_small_boards() {
+        for (width, height) in [(3, 4), (4, 4), (4, 6)] {
+            for seed in 0..3 {
+                assert_eq!(
+                    play("hamiltonian", width, height, seed),
+                    Some(StepOutcome::Won),
+                    "{width}x{height} seed {seed}"
+                );
+            }
+        }
+    }
+}